@@ -0,0 +1,109 @@
+//! Benchmarks of the work done around each ffmpeg run, which adds up in large batches.
+//!
+//! Nothing here spawns a process: probing goes through a [`CommandRunner`] that returns
+//! canned ffprobe output.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mdmp4rev::{CommandRunner, VideoReverser};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use std::process::Output;
+
+/// What `ffprobe -show_streams -show_format -of json` prints for a typical phone clip
+const PROBE_JSON: &str = r#"{
+    "streams": [
+        {"index": 0, "codec_name": "h264", "codec_type": "video",
+         "width": 1920, "height": 1080, "r_frame_rate": "30000/1001",
+         "avg_frame_rate": "30000/1001", "nb_frames": "300"},
+        {"index": 1, "codec_name": "aac", "codec_type": "audio",
+         "r_frame_rate": "0/0", "avg_frame_rate": "0/0"}
+    ],
+    "format": {"duration": "10.010000", "bit_rate": "5000000"}
+}"#;
+
+/// Answers every command with [`PROBE_JSON`]
+struct CannedProbe;
+
+impl CommandRunner for CannedProbe {
+    fn run(&self, _program: &OsStr, _args: &[OsString]) -> std::io::Result<Output> {
+        Ok(Output {
+            status: success(),
+            stdout: PROBE_JSON.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn success() -> std::process::ExitStatus {
+    std::os::unix::process::ExitStatusExt::from_raw(0)
+}
+
+#[cfg(windows)]
+fn success() -> std::process::ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(0)
+}
+
+/// Inputs that don't exist, so name generation doesn't touch the file system beyond a
+/// failed lookup
+fn batch_inputs() -> Vec<PathBuf> {
+    (0..1000)
+        .map(|i| PathBuf::from(format!("missing-dir/holiday.{i:04}.clip.mp4")))
+        .collect()
+}
+
+fn build_ffmpeg_args(c: &mut Criterion) {
+    let reverser = VideoReverser::new();
+    c.bench_function("build_ffmpeg_args", |b| {
+        b.iter(|| reverser.build_ffmpeg_args(black_box("in.mp4"), black_box("out.mp4")))
+    });
+
+    let tuned = VideoReverser::builder()
+        .crf(18)
+        .preset("slow")
+        .speed(2.0)
+        .repeat(3)
+        .extra_args(vec!["-movflags".into(), "+faststart".into()])
+        .build();
+    c.bench_function("build_ffmpeg_args_tuned", |b| {
+        b.iter(|| tuned.build_ffmpeg_args(black_box("in.mp4"), black_box("out.mp4")))
+    });
+}
+
+fn generate_output_filename(c: &mut Criterion) {
+    let inputs = batch_inputs();
+    let reverser = VideoReverser::new();
+    c.bench_function("generate_output_filename_x1000", |b| {
+        b.iter(|| {
+            for input in &inputs {
+                black_box(reverser.generate_output_filename(input));
+            }
+        })
+    });
+
+    let templated = VideoReverser::builder()
+        .output_template("{stem}_{index}_reversed.{ext}")
+        .build();
+    c.bench_function("generate_output_filename_template_x1000", |b| {
+        b.iter(|| {
+            for input in &inputs {
+                black_box(templated.generate_output_filename(input));
+            }
+        })
+    });
+}
+
+fn probe_video_info(c: &mut Criterion) {
+    let reverser = VideoReverser::new_with_runner(Box::new(CannedProbe));
+    c.bench_function("probe_video_info_json", |b| {
+        b.iter(|| reverser.probe_video_info(black_box("in.mp4")).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    build_ffmpeg_args,
+    generate_output_filename,
+    probe_video_info
+);
+criterion_main!(benches);
@@ -0,0 +1,10291 @@
+//! Reverses videos with ffmpeg.
+//!
+//! [`VideoReverser`] is the entry point; the `mdmp4rev` binary is a thin wrapper around it.
+
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum VideoError {
+    #[error("FFmpeg is not installed or not accessible")]
+    FFmpegNotFound,
+    #[error("ffprobe is not installed or not accessible")]
+    FFprobeNotFound,
+    #[error("Invalid input file path: {0}")]
+    InvalidInput(String),
+    #[error("Input is not a video: {0}")]
+    NotAVideo(String),
+    /// Options that contradict each other or are out of range; see
+    /// [`VideoReverserBuilder::validate`]
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    #[error("Failed to process video: {message} ({})", describe_exit(*code))]
+    ProcessingError {
+        /// Broad class of the failure, derived from ffmpeg's log
+        kind: FailureKind,
+        /// ffmpeg's exit code; `None` if it was killed by a signal
+        code: Option<i32>,
+        /// The last meaningful line ffmpeg wrote to stderr
+        message: String,
+        /// The complete stderr log
+        stderr: String,
+        /// The ffmpeg command line that failed, shell-quoted so it can be pasted into a
+        /// terminal to reproduce the failure
+        command: String,
+    },
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Batch worker panicked: {0}")]
+    WorkerPanic(String),
+    #[error("Output file already exists: {}", .0.display())]
+    OutputExists(PathBuf),
+    #[error("ffmpeg did not finish within {0:?}")]
+    Timeout(Duration),
+    #[error("Reversal was cancelled")]
+    Cancelled,
+    #[error("ffmpeg {}.{} is too old; {}.{} or newer is required", found.0, found.1, required.0, required.1)]
+    FFmpegTooOld {
+        found: (u32, u32),
+        required: (u32, u32),
+    },
+    #[error("Input has no audio stream to reverse: {}", .0.display())]
+    NoAudioStream(PathBuf),
+    #[error("Output directory is not writable: {}", .0.display())]
+    OutputNotWritable(PathBuf),
+    #[error("{} is not ffmpeg: {reason}", .path.display())]
+    NotFFmpeg {
+        path: PathBuf,
+        /// What was found instead, e.g. avconv
+        reason: String,
+    },
+    #[error("Output {} failed to decode: {}", .path.display(), last_meaningful_line(.log).unwrap_or("unknown error"))]
+    OutputCorrupt {
+        path: PathBuf,
+        /// Everything ffmpeg reported while decoding the output
+        log: String,
+    },
+}
+
+impl VideoError {
+    /// ffmpeg's exit code for a [`ProcessingError`](Self::ProcessingError).
+    ///
+    /// `None` for other errors, and for a ffmpeg that was killed by a signal rather than
+    /// exiting on its own.
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            VideoError::ProcessingError { code, .. } => *code,
+            _ => None,
+        }
+    }
+}
+
+/// How a failed ffmpeg ended, for error messages
+fn describe_exit(code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("exit code {code}"),
+        None => "killed by a signal".to_string(),
+    }
+}
+
+/// Broad classes of ffmpeg failures that callers may want to handle differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The input is corrupt, truncated or not a media file
+    InvalidInput,
+    /// The ffmpeg build lacks a required encoder or decoder
+    CodecNotFound,
+    /// The output could not be written (disk full, permissions, read-only filesystem)
+    OutputWrite,
+    /// Anything not recognised above
+    Other,
+}
+
+impl FailureKind {
+    /// Classifies a failure from ffmpeg's stderr log
+    fn classify(stderr: &str) -> Self {
+        const OUTPUT_WRITE: &[&str] = &[
+            "no space left on device",
+            "disk quota exceeded",
+            "read-only file system",
+            "permission denied",
+            "error writing",
+        ];
+        const CODEC_NOT_FOUND: &[&str] = &[
+            "unknown encoder",
+            "unknown decoder",
+            "encoder not found",
+            "decoder not found",
+            "codec not currently supported",
+        ];
+        const INVALID_INPUT: &[&str] = &[
+            "invalid data found when processing input",
+            "moov atom not found",
+            "could not find codec parameters",
+            "error while decoding",
+        ];
+
+        let stderr = stderr.to_lowercase();
+        let matches = |needles: &[&str]| needles.iter().any(|n| stderr.contains(n));
+        if matches(OUTPUT_WRITE) {
+            FailureKind::OutputWrite
+        } else if matches(CODEC_NOT_FOUND) {
+            FailureKind::CodecNotFound
+        } else if matches(INVALID_INPUT) {
+            FailureKind::InvalidInput
+        } else {
+            FailureKind::Other
+        }
+    }
+}
+
+/// Non-fatal problems met while reversing; each is also logged at warn level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// ffmpeg's version couldn't be read, so it was assumed to meet this minimum
+    UnknownFFmpegVersion { assumed: (u32, u32) },
+    /// ffprobe couldn't be run, so the input wasn't validated
+    FFprobeUnavailable,
+    /// The input has no audio stream, so only the video was reversed
+    NoAudioStream,
+    /// The source encoding couldn't be read, so ffmpeg's defaults were used
+    SourceEncodingUnknown,
+    /// CRF and preset were ignored because this encoder may not support them
+    QualityOptionsIgnored { encoder: String },
+    /// This extra ffmpeg argument looks like an additional output
+    SuspiciousExtraArgument(String),
+    /// The output couldn't be probed, so its duration and resolution are unknown
+    OutputNotProbed,
+    /// The video seems to have a variable frame rate, which reverses with stutter unless
+    /// [`VideoReverserBuilder::normalize_fps`] is set
+    VariableFrameRate,
+    /// The source's pixel format doesn't play in browsers and QuickTime once encoded to
+    /// H.264 in an MP4 or MOV, so the video was converted to `yuv420p`; set
+    /// [`VideoReverserBuilder::pixel_format`] to choose another
+    PixelFormatConverted { from: String },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::UnknownFFmpegVersion { assumed } => write!(
+                f,
+                "could not read the ffmpeg version; assuming it is at least {}.{}",
+                assumed.0, assumed.1
+            ),
+            Warning::FFprobeUnavailable => {
+                write!(f, "ffprobe is not available; skipping input validation")
+            }
+            Warning::NoAudioStream => {
+                write!(f, "the input has no audio stream; reversing the video only")
+            }
+            Warning::SourceEncodingUnknown => write!(
+                f,
+                "could not read the source encoding with ffprobe; using ffmpeg defaults"
+            ),
+            Warning::QualityOptionsIgnored { encoder } => write!(
+                f,
+                "encoder {} may not support -crf/-preset; ignoring them",
+                encoder
+            ),
+            Warning::SuspiciousExtraArgument(arg) => write!(
+                f,
+                "extra ffmpeg argument {:?} looks like an additional output",
+                arg
+            ),
+            Warning::OutputNotProbed => write!(f, "could not probe the output with ffprobe"),
+            Warning::VariableFrameRate => write!(
+                f,
+                "the input seems to have a variable frame rate; set a constant one to avoid stutter"
+            ),
+            Warning::PixelFormatConverted { from } => write!(
+                f,
+                "pixel format {from} won't play in browsers or QuickTime; converting to {COMPATIBLE_PIXEL_FORMAT}"
+            ),
+        }
+    }
+}
+
+/// Logs `warning` and records it for the caller
+fn warn(warnings: &mut Vec<Warning>, warning: Warning) {
+    log::warn!("{warning}");
+    warnings.push(warning);
+}
+
+/// Limits and output handling applied to a running command
+#[derive(Debug, Clone, Default)]
+pub struct RunControl {
+    /// Kill the command if it hasn't finished after this long; the run then fails
+    /// with [`std::io::ErrorKind::TimedOut`]
+    pub timeout: Option<Duration>,
+    /// Kill the command once this is set to `true`; the run then fails with
+    /// [`std::io::ErrorKind::Interrupted`]
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Copy the command's stderr to this process's stderr as it arrives; it is still
+    /// captured in the returned output
+    pub echo_stderr: bool,
+}
+
+impl RunControl {
+    /// How often a runner checks the cancel flag while the command runs
+    pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Whether anything may need to stop the command early
+    pub fn is_limited(&self) -> bool {
+        self.timeout.is_some() || self.cancel.is_some()
+    }
+
+    /// The error to stop with, if the run was cancelled or has passed `deadline`
+    fn interruption(&self, deadline: Option<Instant>) -> Option<std::io::Error> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            Some(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "command cancelled",
+            ))
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "command timed out",
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// How long to block before checking [`interruption`](Self::interruption) again
+    fn next_wait(&self, deadline: Option<Instant>) -> Option<Duration> {
+        let until_deadline = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        match (until_deadline, self.cancel.is_some()) {
+            (Some(wait), true) => Some(wait.min(Self::CANCEL_POLL_INTERVAL)),
+            (Some(wait), false) => Some(wait),
+            (None, true) => Some(Self::CANCEL_POLL_INTERVAL),
+            (None, false) => None,
+        }
+    }
+}
+
+/// Trait to abstract system command execution.
+///
+/// Every ffmpeg and ffprobe run of a [`VideoReverser`] goes through its runner, except
+/// for the `tokio` based async API. [`RealCommandRunner`] spawns real processes; a test
+/// can pass its own to [`VideoReverserBuilder::runner`] to check the arguments and
+/// return canned output without ffmpeg installed:
+///
+/// ```
+/// use mdmp4rev::{CommandRunner, VideoReverser};
+/// use std::ffi::{OsStr, OsString};
+/// use std::process::Output;
+///
+/// struct Fake;
+///
+/// impl CommandRunner for Fake {
+///     fn run(&self, program: &OsStr, _args: &[OsString]) -> std::io::Result<Output> {
+///         // Pretend neither tool is installed
+///         Err(std::io::Error::new(std::io::ErrorKind::NotFound, program.to_string_lossy()))
+///     }
+/// }
+///
+/// let reverser = VideoReverser::builder().runner(Box::new(Fake)).build();
+/// assert!(reverser.ffmpeg_version().is_err());
+/// ```
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &OsStr, args: &[OsString]) -> std::io::Result<std::process::Output>;
+
+    /// Runs the command, passing each line of stdout to `on_line` as it is produced.
+    ///
+    /// The default implementation waits for the command to finish and then replays its
+    /// stdout, and its stderr if `control` echoes it; it can't enforce the limits in
+    /// `control`.
+    fn run_streaming(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        on_line: &mut dyn FnMut(&str),
+        control: &RunControl,
+    ) -> std::io::Result<std::process::Output> {
+        let output = self.run(program, args)?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .for_each(&mut *on_line);
+        if control.echo_stderr {
+            let _ = std::io::stderr().write_all(&output.stderr);
+        }
+        Ok(output)
+    }
+
+    /// Runs the command with its stdin and stdout connected to this process's own,
+    /// capturing only stderr.
+    ///
+    /// The default implementation falls back to [`run`](Self::run).
+    fn run_piped(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+    ) -> std::io::Result<std::process::Output> {
+        self.run(program, args)
+    }
+}
+
+/// Real implementation using std::process::Command.
+///
+/// Programs are spawned directly, never through a shell, so every argument reaches them
+/// as is: paths with spaces, quotes, `$` or non-ASCII characters need no escaping.
+pub struct RealCommandRunner;
+
+impl CommandRunner for RealCommandRunner {
+    fn run(&self, program: &OsStr, args: &[OsString]) -> std::io::Result<std::process::Output> {
+        Command::new(program).args(args).output()
+    }
+
+    fn run_streaming(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+        on_line: &mut dyn FnMut(&str),
+        control: &RunControl,
+    ) -> std::io::Result<std::process::Output> {
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // A run that may be killed gets its own process group so the kill also reaches
+        // anything it spawned
+        #[cfg(unix)]
+        if control.is_limited() {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let mut child = command.spawn()?;
+
+        // Drain both pipes on their own threads so a chatty child can't block on a full
+        // pipe, and so the limits can be checked while waiting for output
+        let mut stderr_pipe = child.stderr.take().expect("stderr is piped");
+        let echo = control.echo_stderr;
+        let stderr_reader = std::thread::spawn(move || {
+            let mut stderr = Vec::new();
+            if !echo {
+                return stderr_pipe.read_to_end(&mut stderr).map(|_| stderr);
+            }
+            let mut chunk = [0; 4096];
+            loop {
+                let read = stderr_pipe.read(&mut chunk)?;
+                if read == 0 {
+                    return Ok(stderr);
+                }
+                // A closed terminal shouldn't fail the run
+                let _ = std::io::stderr().write_all(&chunk[..read]);
+                stderr.extend_from_slice(&chunk[..read]);
+            }
+        });
+        let (sender, lines) = mpsc::channel();
+        let stdout_pipe = child.stdout.take().expect("stdout is piped");
+        let stdout_reader = std::thread::spawn(move || -> std::io::Result<()> {
+            let mut reader = BufReader::new(stdout_pipe);
+            loop {
+                let mut line = Vec::new();
+                if reader.read_until(b'\n', &mut line)? == 0 || sender.send(line).is_err() {
+                    return Ok(());
+                }
+            }
+        });
+
+        let deadline = control.timeout.map(|timeout| Instant::now() + timeout);
+        let mut stdout = Vec::new();
+        loop {
+            let received = match control.next_wait(deadline) {
+                Some(wait) => lines.recv_timeout(wait),
+                None => lines
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(line) => {
+                    on_line(String::from_utf8_lossy(&line).trim_end());
+                    stdout.extend(line);
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            if let Some(err) = control.interruption(deadline) {
+                stop_process_tree(&mut child, &err);
+                return Err(err);
+            }
+        }
+
+        // stdout is closed, but the child may still be running until it exits
+        let status = if control.is_limited() {
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if let Some(err) = control.interruption(deadline) {
+                    stop_process_tree(&mut child, &err);
+                    return Err(err);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        } else {
+            child.wait()?
+        };
+        stdout_reader
+            .join()
+            .map_err(|_| std::io::Error::other("stdout reader panicked"))??;
+        let stderr = stderr_reader
+            .join()
+            .map_err(|_| std::io::Error::other("stderr reader panicked"))??;
+        Ok(std::process::Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn run_piped(
+        &self,
+        program: &OsStr,
+        args: &[OsString],
+    ) -> std::io::Result<std::process::Output> {
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped())
+            .output()
+    }
+}
+
+/// How long a cancelled child gets to exit after being interrupted before it is killed
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// Stops a child that ran into `err`. On Unix a cancelled child is first sent SIGINT, as
+/// a Ctrl-C would, and given [`CANCEL_GRACE`] to exit; a timed-out one is killed at once.
+fn stop_process_tree(child: &mut std::process::Child, err: &std::io::Error) {
+    #[cfg(unix)]
+    if err.kind() == std::io::ErrorKind::Interrupted {
+        let pgid = child.id() as libc::pid_t;
+        unsafe {
+            libc::kill(-pgid, libc::SIGINT);
+        }
+        let deadline = Instant::now() + CANCEL_GRACE;
+        while Instant::now() < deadline && matches!(child.try_wait(), Ok(None)) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = err;
+    kill_process_tree(child);
+}
+
+/// Kills a timed-out or cancelled child and reaps it; on Unix the whole process group goes with it
+fn kill_process_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // The child leads its own group (see `process_group(0)` above)
+        let pgid = child.id() as libc::pid_t;
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// A fully resolved ffmpeg invocation: the program followed by its arguments in order.
+///
+/// `Display` renders it as a POSIX-shell-quoted command line that can be pasted into a terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegCommand {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+}
+
+impl std::fmt::Display for FfmpegCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", shell_quote(&self.program.to_string_lossy()))?;
+        for arg in &self.args {
+            write!(f, " {}", shell_quote(&arg.to_string_lossy()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes `arg` for a POSIX shell, leaving plain words untouched
+fn shell_quote(arg: &str) -> std::borrow::Cow<'_, str> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=+,@%^".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.into()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''")).into()
+    }
+}
+
+/// What a finished reversal produced, as reported by ffprobe on the output
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReverseOutcome {
+    pub path: PathBuf,
+    /// Container duration, if ffprobe could read it
+    pub duration: Option<Duration>,
+    /// Width and height of the first video stream, if ffprobe could read them
+    pub resolution: Option<(u32, u32)>,
+    /// Size of the output file in bytes
+    pub size: u64,
+    /// Wall-clock time ffmpeg spent on the reversal; zero if it didn't run
+    pub elapsed: Duration,
+    /// Whether a file already at the output path was replaced, under
+    /// [`OverwritePolicy::Always`]
+    pub overwrote_existing: bool,
+    /// Non-fatal problems met along the way
+    pub warnings: Vec<Warning>,
+}
+
+impl ReverseOutcome {
+    /// ffprobe arguments that print `width=`, `height=` and `duration=` lines
+    fn ffprobe_args(path: &Path) -> Vec<OsString> {
+        vec![
+            "-v".into(),
+            "error".into(),
+            "-select_streams".into(),
+            "v:0".into(),
+            "-show_entries".into(),
+            "stream=width,height:format=duration".into(),
+            "-of".into(),
+            "default=noprint_wrappers=1".into(),
+            path.into(),
+        ]
+    }
+
+    /// Seconds of video reversed per second of processing; above 1.0 is faster than real time.
+    ///
+    /// `None` if the duration is unknown or ffmpeg didn't run.
+    pub fn realtime_factor(&self) -> Option<f64> {
+        let duration = self.duration?;
+        if self.elapsed.is_zero() {
+            return None;
+        }
+        Some(duration.as_secs_f64() / self.elapsed.as_secs_f64())
+    }
+
+    /// Fills the probed fields from the output of [`ffprobe_args`](Self::ffprobe_args)
+    fn parse(path: PathBuf, size: u64, elapsed: Duration, stdout: &str) -> Self {
+        let field = |name: &str| {
+            stdout
+                .lines()
+                .filter_map(|line| line.trim().split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value)
+        };
+        let dimension = |name: &str| field(name).and_then(|value| value.parse::<u32>().ok());
+        Self {
+            path,
+            duration: field("duration")
+                .and_then(|value| value.parse::<f64>().ok())
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+            resolution: dimension("width").zip(dimension("height")),
+            size,
+            elapsed,
+            overwrote_existing: false,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// What a reversal reports to a [`MetricsSink`] when it ends, successfully or not
+#[derive(Debug)]
+pub struct ReverseMetrics<'a> {
+    /// The input file; the first one for [`VideoReverser::reverse_concat`], and `pipe:0`
+    /// for [`VideoReverser::reverse_stream`]
+    pub input: &'a Path,
+    /// The output file; the `%0Nd` pattern for an image sequence, and `pipe:1` for
+    /// [`VideoReverser::reverse_stream`]
+    pub output: &'a Path,
+    /// Size of the input in bytes, if it could be read
+    pub input_size: Option<u64>,
+    /// Size of the output in bytes; `None` on failure, in dry-run mode and for outputs
+    /// that aren't one file
+    pub output_size: Option<u64>,
+    /// Wall-clock time of the whole call, including validation and probing
+    pub elapsed: Duration,
+    /// Why the reversal failed; `None` on success
+    pub error: Option<&'a VideoError>,
+}
+
+/// Receives [`ReverseMetrics`] at the end of every reversal, so they can be forwarded
+/// to a metrics backend such as Prometheus or StatsD.
+///
+/// Closures taking `&ReverseMetrics` implement it. It is called on the thread that ran
+/// the reversal, which waits for it, so keep it quick.
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &ReverseMetrics);
+}
+
+impl<F: Fn(&ReverseMetrics) + Send + Sync> MetricsSink for F {
+    fn record(&self, metrics: &ReverseMetrics) {
+        self(metrics)
+    }
+}
+
+/// One ffmpeg progress report, as delivered by [`VideoReverser::reverse_video_events`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProgressUpdate {
+    /// How far into the output ffmpeg has got
+    pub time: Duration,
+    /// Frames encoded per second, if ffmpeg reported it
+    pub fps: Option<f64>,
+    /// Encoding speed relative to playback, e.g. `2.5` for ffmpeg's `speed=2.5x`
+    pub speed: Option<f64>,
+    /// Completion fraction (0.0 to 1.0); `None` if the output duration is unknown
+    pub fraction: Option<f64>,
+}
+
+/// What [`VideoReverser::reverse_video_events`] reports while a reversal runs
+#[derive(Debug)]
+pub enum ReverseEvent {
+    /// The reversal has begun; always the first event
+    Started,
+    /// ffmpeg reported progress, at most about once per second
+    Progress(ProgressUpdate),
+    /// The reversal succeeded; always the last event
+    Finished(ReverseOutcome),
+    /// The reversal failed; always the last event
+    Failed(VideoError),
+}
+
+/// Blocking iterator over the events of a reversal running on a background thread,
+/// returned by [`VideoReverser::reverse_video_events`]
+pub struct ReverseEvents {
+    events: mpsc::Receiver<ReverseEvent>,
+    worker: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Iterator for ReverseEvents {
+    type Item = ReverseEvent;
+
+    fn next(&mut self) -> Option<ReverseEvent> {
+        match self.events.recv() {
+            Ok(event) => Some(event),
+            // The worker only hangs up without a final event if it panicked
+            Err(_) => self.worker.take()?.join().err().map(|payload| {
+                ReverseEvent::Failed(VideoError::WorkerPanic(panic_message(payload.as_ref())))
+            }),
+        }
+    }
+}
+
+/// Size and modification time of an input, as recorded in an [`InputCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct InputSignature {
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Last modification time reported by the filesystem
+    pub modified: std::time::SystemTime,
+}
+
+impl InputSignature {
+    /// Reads the signature of the file at `path`
+    pub fn of<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// Signatures of the inputs an incremental batch has already reversed, used by
+/// [`VideoReverser::reverse_directory_incremental`] to skip unchanged files.
+///
+/// The cache lives in memory; [`load`](Self::load) and [`save`](Self::save) keep it in a
+/// JSON sidecar file between runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct InputCache {
+    entries: std::collections::BTreeMap<PathBuf, InputSignature>,
+}
+
+impl InputCache {
+    /// Reads a cache written by [`save`](Self::save); a missing file gives an empty cache
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, VideoError> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).map_err(std::io::Error::from)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the cache as JSON to `path`, replacing any existing file.
+    ///
+    /// Fails with [`VideoError::IoError`] if a recorded path is not valid UTF-8.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), VideoError> {
+        let json = serde_json::to_vec_pretty(self).map_err(std::io::Error::from)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// The signature recorded for `input`, if any
+    pub fn get<P: AsRef<Path>>(&self, input: P) -> Option<&InputSignature> {
+        self.entries.get(input.as_ref())
+    }
+
+    /// Records `signature` for `input`, replacing an earlier one
+    pub fn insert(&mut self, input: impl Into<PathBuf>, signature: InputSignature) {
+        self.entries.insert(input.into(), signature);
+    }
+
+    /// Forgets `input`, so the next incremental run reverses it again
+    pub fn remove<P: AsRef<Path>>(&mut self, input: P) -> Option<InputSignature> {
+        self.entries.remove(input.as_ref())
+    }
+
+    /// Recorded inputs and their signatures, sorted by path
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &InputSignature)> {
+        self.entries
+            .iter()
+            .map(|(path, signature)| (path.as_path(), signature))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// How an input of [`VideoReverser::reverse_directory_incremental`] was handled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchStatus {
+    /// ffmpeg reversed the input to this path
+    Reversed(PathBuf),
+    /// The input was unchanged since its last reversal and this output already existed
+    Skipped(PathBuf),
+}
+
+impl BatchStatus {
+    /// The output path, whether it was written now or by an earlier run
+    pub fn path(&self) -> &Path {
+        match self {
+            BatchStatus::Reversed(path) | BatchStatus::Skipped(path) => path,
+        }
+    }
+}
+
+/// One stream of a media file, as listed by [`VideoReverser::list_streams`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// Position of the stream in the file, as taken by
+    /// [`VideoReverserBuilder::video_stream`] and [`VideoReverserBuilder::audio_stream`]
+    pub index: usize,
+    /// `video`, `audio`, `subtitle` or `data`, as ffprobe reports it
+    pub codec_type: String,
+    pub codec_name: Option<String>,
+    /// Degrees the picture has to be turned clockwise to display upright: 0, 90, 180 or
+    /// 270, from the display matrix or the older `rotate` tag
+    pub rotation: u32,
+    /// Whether the average frame rate differs from the base rate, as it does when frames
+    /// aren't evenly spaced, e.g. in screen recordings
+    pub variable_frame_rate: bool,
+    /// Pixel format of a video stream, e.g. `yuv420p`
+    pub pixel_format: Option<String>,
+    /// Width of a video stream in pixels, before any rotation
+    pub width: Option<u32>,
+    /// Height of a video stream in pixels, before any rotation
+    pub height: Option<u32>,
+}
+
+impl StreamInfo {
+    /// Parses the `[STREAM]` blocks of `ffprobe -show_streams` output
+    fn parse_all(stdout: &str) -> Vec<Self> {
+        let mut streams = Vec::new();
+        let (mut base_rate, mut average_rate) = (None, None);
+        for line in stdout.lines().map(str::trim) {
+            if line == "[STREAM]" {
+                (base_rate, average_rate) = (None, None);
+                streams.push(StreamInfo {
+                    index: streams.len(),
+                    codec_type: String::new(),
+                    codec_name: None,
+                    rotation: 0,
+                    variable_frame_rate: false,
+                    pixel_format: None,
+                    width: None,
+                    height: None,
+                });
+            }
+            let (Some(stream), Some((key, value))) = (streams.last_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            match key {
+                "index" => stream.index = value.parse().unwrap_or(stream.index),
+                "codec_type" => stream.codec_type = value.to_string(),
+                "codec_name" if !value.is_empty() && value != "N/A" => {
+                    stream.codec_name = Some(value.to_string())
+                }
+                // The display matrix turns counterclockwise, the tag clockwise
+                "rotation" => stream.rotation = quarter_turn(value, -1.0),
+                "TAG:rotate" => stream.rotation = quarter_turn(value, 1.0),
+                "pix_fmt" if !["", "N/A", "unknown"].contains(&value) => {
+                    stream.pixel_format = Some(value.to_string())
+                }
+                "width" => stream.width = value.parse().ok().filter(|width| *width > 0),
+                "height" => stream.height = value.parse().ok().filter(|height| *height > 0),
+                "r_frame_rate" => base_rate = parse_rate(value),
+                "avg_frame_rate" => average_rate = parse_rate(value),
+                _ => {}
+            }
+            // Both rates come before the end of the block
+            if let (Some(base), Some(average)) = (base_rate, average_rate) {
+                stream.variable_frame_rate = (base - average).abs() > base * 0.01;
+                (base_rate, average_rate) = (None, None);
+            }
+        }
+        streams
+    }
+}
+
+/// A frame rate such as `30000/1001` in frames per second, or `None` for ffprobe's
+/// `0/0` placeholder
+fn parse_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/')?;
+    let (num, den) = (num.parse::<f64>().ok()?, den.parse::<f64>().ok()?);
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+/// `degrees` times `direction` as a clockwise 0, 90, 180 or 270, or 0 if it doesn't parse
+fn quarter_turn(degrees: &str, direction: f64) -> u32 {
+    match degrees.parse::<f64>() {
+        Ok(degrees) => ((degrees * direction / 90.0).round() as i64).rem_euclid(4) as u32 * 90,
+        Err(_) => 0,
+    }
+}
+
+/// The filters that turn a picture needing `rotation` clockwise degrees upright
+fn rotation_filters(rotation: u32) -> &'static [&'static str] {
+    match rotation {
+        90 => &["transpose=clock"],
+        180 => &["hflip", "vflip"],
+        270 => &["transpose=cclock"],
+        _ => &[],
+    }
+}
+
+/// What ffprobe found out about an input while validating it
+#[derive(Debug, Clone)]
+struct ProbedInput {
+    has_audio: bool,
+    /// Clockwise degrees the reversed video stream has to be turned
+    rotation: u32,
+    variable_frame_rate: bool,
+    /// Pixel format of the reversed video stream
+    pixel_format: Option<String>,
+    /// Stored width and height of the reversed video stream
+    dimensions: Option<(u32, u32)>,
+}
+
+impl ProbedInput {
+    /// What is assumed when the input isn't probed
+    const ASSUMED: Self = Self {
+        has_audio: true,
+        rotation: 0,
+        variable_frame_rate: false,
+        pixel_format: None,
+        dimensions: None,
+    };
+}
+
+/// What [`VideoReverser::probe_video_info`] reports about a file. Fields ffprobe
+/// doesn't report are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VideoInfo {
+    /// Container duration
+    pub duration: Option<Duration>,
+    /// Width of the first video stream in pixels
+    pub width: Option<u32>,
+    /// Height of the first video stream in pixels
+    pub height: Option<u32>,
+    /// Frames per second of the first video stream as `(numerator, denominator)`, e.g.
+    /// `(30000, 1001)` for NTSC
+    pub frame_rate: Option<(u32, u32)>,
+    /// Frames in the first video stream; computed from the duration and frame rate when
+    /// the container doesn't record it
+    pub frame_count: Option<u64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    /// Overall bitrate in bits per second
+    pub bit_rate: Option<u64>,
+}
+
+impl VideoInfo {
+    /// ffprobe arguments that print every stream and the container as JSON
+    fn ffprobe_args(path: &Path) -> Vec<OsString> {
+        vec![
+            "-v".into(),
+            "error".into(),
+            "-show_streams".into(),
+            "-show_format".into(),
+            "-of".into(),
+            "json".into(),
+            path.into(),
+        ]
+    }
+
+    /// Parses the output of [`ffprobe_args`](Self::ffprobe_args)
+    fn parse(stdout: &str) -> Result<Self, serde_json::Error> {
+        // ffprobe prints most numbers as strings
+        #[derive(serde::Deserialize)]
+        struct Probe {
+            #[serde(default)]
+            streams: Vec<Stream>,
+            format: Option<Format>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Stream {
+            codec_type: Option<String>,
+            codec_name: Option<String>,
+            width: Option<u32>,
+            height: Option<u32>,
+            avg_frame_rate: Option<String>,
+            r_frame_rate: Option<String>,
+            nb_frames: Option<String>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Format {
+            duration: Option<String>,
+            bit_rate: Option<String>,
+        }
+
+        let probe: Probe = serde_json::from_str(stdout)?;
+        let first = |codec_type: &str| {
+            probe
+                .streams
+                .iter()
+                .find(|stream| stream.codec_type.as_deref() == Some(codec_type))
+        };
+        let video = first("video");
+        let format = probe.format.as_ref();
+        let duration = format
+            .and_then(|format| format.duration.as_deref()?.parse::<f64>().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+        // avg_frame_rate is 0/0 for some streams, so r_frame_rate is the fallback
+        let frame_rate = video.and_then(|video| {
+            [&video.avg_frame_rate, &video.r_frame_rate]
+                .into_iter()
+                .filter_map(|rate| {
+                    let (num, den) = rate.as_deref()?.split_once('/')?;
+                    Some((num.parse::<u32>().ok()?, den.parse::<u32>().ok()?))
+                })
+                .find(|(num, den)| *num > 0 && *den > 0)
+        });
+        let frame_count = video
+            .and_then(|video| video.nb_frames.as_deref()?.parse().ok())
+            .or_else(|| {
+                let (num, den) = frame_rate?;
+                Some((duration?.as_secs_f64() * f64::from(num) / f64::from(den)).round() as u64)
+            });
+        Ok(Self {
+            duration,
+            width: video.and_then(|video| video.width),
+            height: video.and_then(|video| video.height),
+            frame_rate,
+            frame_count,
+            video_codec: video.and_then(|video| video.codec_name.clone()),
+            audio_codec: first("audio").and_then(|audio| audio.codec_name.clone()),
+            bit_rate: format.and_then(|format| format.bit_rate.as_deref()?.parse().ok()),
+        })
+    }
+
+    /// Frames per second as a decimal, if the frame rate is known
+    pub fn fps(&self) -> Option<f64> {
+        let (num, den) = self.frame_rate?;
+        Some(f64::from(num) / f64::from(den))
+    }
+}
+
+/// Codec and bitrate of the first video and audio streams of a source file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SourceEncoding {
+    video_codec: Option<String>,
+    video_bitrate: Option<u64>,
+    audio_codec: Option<String>,
+    audio_bitrate: Option<u64>,
+}
+
+impl SourceEncoding {
+    /// ffprobe arguments that print one `key=value|...` line per stream
+    fn ffprobe_args(input_path: &Path) -> Vec<OsString> {
+        vec![
+            "-v".into(),
+            "error".into(),
+            "-show_entries".into(),
+            "stream=codec_type,codec_name,bit_rate".into(),
+            "-of".into(),
+            "compact=p=0".into(),
+            input_path.into(),
+        ]
+    }
+
+    /// Parses the output of [`ffprobe_args`](Self::ffprobe_args)
+    fn parse(stdout: &str) -> Self {
+        let mut encoding = SourceEncoding::default();
+        for line in stdout.lines() {
+            let field = |name: &str| {
+                line.split('|')
+                    .filter_map(|entry| entry.split_once('='))
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value)
+                    .filter(|value| !value.is_empty() && *value != "N/A")
+            };
+            let codec = field("codec_name").map(str::to_string);
+            let bitrate = field("bit_rate").and_then(|b| b.parse().ok());
+            match field("codec_type") {
+                Some("video") if encoding.video_codec.is_none() => {
+                    encoding.video_codec = codec;
+                    encoding.video_bitrate = bitrate;
+                }
+                Some("audio") if encoding.audio_codec.is_none() => {
+                    encoding.audio_codec = codec;
+                    encoding.audio_bitrate = bitrate;
+                }
+                _ => {}
+            }
+        }
+        encoding
+    }
+
+    /// ffmpeg arguments that mirror this encoding for the streams being re-encoded.
+    ///
+    /// Codec names are passed straight to `-c:v`/`-c:a`; ffmpeg resolves them to
+    /// its default encoder for that codec (e.g. `h264` to libx264).
+    fn ffmpeg_args(&self, encode_video: bool, encode_audio: bool) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        let mut push = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.extend([flag.into(), value.into()]);
+            }
+        };
+        if encode_video {
+            push("-c:v", self.video_codec.clone());
+            push("-b:v", self.video_bitrate.map(|b| b.to_string()));
+        }
+        if encode_audio {
+            push("-c:a", self.audio_codec.clone());
+            push("-b:a", self.audio_bitrate.map(|b| b.to_string()));
+        }
+        args
+    }
+}
+
+/// A time window of the input, used by segment reversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: Duration,
+    end: Duration,
+}
+
+/// Fade-in and fade-out at the ends of an output
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fade {
+    /// Length of each fade in seconds
+    length: f64,
+    /// Length of the output in seconds, which places the fade-out
+    clip: f64,
+}
+
+impl Fade {
+    /// `filter=t=in` then `filter=t=out`, for `fade` (video) or `afade` (audio)
+    fn filters(self, filter: &str) -> String {
+        format!(
+            "{filter}=t=in:st=0:d={length},{filter}=t=out:st={out}:d={length}",
+            length = self.length,
+            out = self.clip - self.length
+        )
+    }
+}
+
+/// Frame rate and width of an animated GIF output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Gif {
+    fps: u32,
+    width: Option<u32>,
+}
+
+impl Gif {
+    /// Reverses, fades, resamples and scales, then builds a palette from the clip itself
+    /// so the 256 GIF colors fit the content
+    fn filter(self, fade: Option<Fade>) -> String {
+        let width = self.width.map_or("iw".to_string(), |w| w.to_string());
+        let fade = fade.map_or(String::new(), |fade| format!("{},", fade.filters("fade")));
+        format!(
+            "reverse,{}fps={},scale={}:-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse",
+            fade, self.fps, width
+        )
+    }
+}
+
+/// Filtergraph that plays the original on the left and the reversed clip on the right,
+/// each labelled in its top-left corner
+const SIDE_BY_SIDE_FILTER: &str = "[0:v]split[fwd][rev];\
+    [fwd]drawtext=text=Original:x=10:y=10:fontsize=24:fontcolor=white:box=1:boxcolor=black@0.5[left];\
+    [rev]reverse,drawtext=text=Reversed:x=10:y=10:fontsize=24:fontcolor=white:box=1:boxcolor=black@0.5[right];\
+    [left][right]hstack";
+
+/// Clips longer than this get a warning before a boomerang, since every copy of the
+/// clip is held in memory by the reverse filters
+const BOOMERANG_WARN_SECONDS: f64 = 60.0;
+
+/// Number of forward-then-backward plays in a boomerang
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Boomerang {
+    loops: u32,
+}
+
+impl Boomerang {
+    /// Splits the clip into one copy per play, reverses every second copy and
+    /// concatenates them, with the audio treated the same way if `audio` is set.
+    /// The result, faded as a whole, is labelled `[v]` (and `[a]`).
+    fn filter(self, audio: bool, fade: Option<Fade>) -> String {
+        let copies = self.loops * 2;
+        let mut graph = String::new();
+        let mut segments = String::new();
+        let streams: &[(&str, &str, &str)] = if audio {
+            &[("v", "split", "reverse"), ("a", "asplit", "areverse")]
+        } else {
+            &[("v", "split", "reverse")]
+        };
+        for (kind, split, reverse) in streams {
+            graph.push_str(&format!("[0:{kind}]{split}={copies}"));
+            for i in 0..copies {
+                graph.push_str(&format!("[{kind}{i}]"));
+            }
+            graph.push(';');
+            for i in (1..copies).step_by(2) {
+                graph.push_str(&format!("[{kind}{i}]{reverse}[{kind}r{i}];"));
+            }
+        }
+        for i in 0..copies {
+            let direction = if i % 2 == 1 { "r" } else { "" };
+            for (kind, _, _) in streams {
+                segments.push_str(&format!("[{kind}{direction}{i}]"));
+            }
+        }
+        graph.push_str(&segments);
+        let Some(fade) = fade else {
+            if audio {
+                graph.push_str(&format!("concat=n={copies}:v=1:a=1[v][a]"));
+            } else {
+                graph.push_str(&format!("concat=n={copies}:v=1:a=0[v]"));
+            }
+            return graph;
+        };
+        if audio {
+            graph.push_str(&format!(
+                "concat=n={copies}:v=1:a=1[vc][ac];[vc]{}[v];[ac]{}[a]",
+                fade.filters("fade"),
+                fade.filters("afade")
+            ));
+        } else {
+            graph.push_str(&format!(
+                "concat=n={copies}:v=1:a=0[vc];[vc]{}[v]",
+                fade.filters("fade")
+            ));
+        }
+        graph
+    }
+}
+
+/// Still-image formats for [`VideoReverser::reverse_to_image_sequence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    /// Lossless, the usual choice for compositing
+    #[default]
+    Png,
+    /// Lossy but small, written at ffmpeg's highest JPEG quality
+    Jpeg,
+    Tiff,
+}
+
+impl ImageFormat {
+    /// File extensions of the format, the one for new files first
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ImageFormat::Png => &["png"],
+            ImageFormat::Jpeg => &["jpg", "jpeg"],
+            ImageFormat::Tiff => &["tiff", "tif"],
+        }
+    }
+}
+
+/// Splits an image sequence pattern such as `frame_%04d.png` around its `%0Nd` token,
+/// returning the text before it, the digit count and the text after it
+fn split_sequence_pattern(pattern: &str) -> Option<(&str, usize, &str)> {
+    let (prefix, rest) = pattern.split_once("%0")?;
+    let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+    let width = rest[..digits].parse().ok().filter(|width| *width > 0)?;
+    let suffix = rest[digits..].strip_prefix('d')?;
+    // ffmpeg would read any other % as a second token
+    (!prefix.contains('%') && !suffix.contains('%')).then_some((prefix, width, suffix))
+}
+
+/// What a job writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    /// The streams selected by the mode, reversed
+    Reverse,
+    /// An animated GIF of the reversed video
+    Gif(Gif),
+    /// The original and reversed video next to each other
+    SideBySide,
+    /// The clip played forward, then backward
+    Boomerang(Boomerang),
+    /// A single frame taken at this time, not reversed
+    Thumbnail(Duration),
+    /// The reversed video as numbered still images
+    ImageSequence(ImageFormat),
+    /// The reversed audio alone, in an audio file
+    AudioExtract,
+}
+
+/// Everything a single reverse call needs to build its ffmpeg command
+#[derive(Debug, Clone, Copy)]
+struct Job<'a> {
+    input: &'a Path,
+    output: &'a Path,
+    mode: ReverseMode,
+    segment: Option<Segment>,
+    /// Output muxer, for outputs whose format can't be guessed from a file extension
+    format: Option<&'a str>,
+    effect: Effect,
+    /// Whether the input has an audio stream; assumed when it wasn't probed
+    has_audio: bool,
+    /// The pass of a two-pass encode this job runs
+    pass: Option<Pass<'a>>,
+    /// Fades at the ends of the output, placed from its probed length
+    fade: Option<Fade>,
+    /// Clockwise degrees to turn the video so it displays upright
+    rotation: u32,
+    /// Pixel format chosen for the probed source when none is configured
+    pixel_format: Option<&'a str>,
+    /// Whether the input is a list of files for ffmpeg's concat demuxer
+    concat: bool,
+    /// Width and height of the centred window the upright video is cropped to
+    crop: Option<(u32, u32)>,
+}
+
+/// One run of a two-pass encode
+#[derive(Debug, Clone, Copy)]
+struct Pass<'a> {
+    /// 1 gathers statistics, 2 writes the output
+    number: u8,
+    /// Prefix of the statistics files shared by both passes
+    log: &'a Path,
+}
+
+impl<'a> Job<'a> {
+    /// A full-length reversal
+    fn new(input: &'a Path, output: &'a Path, mode: ReverseMode) -> Self {
+        Self {
+            input,
+            output,
+            mode,
+            segment: None,
+            format: None,
+            effect: Effect::Reverse,
+            has_audio: true,
+            pass: None,
+            fade: None,
+            rotation: 0,
+            pixel_format: None,
+            concat: false,
+            crop: None,
+        }
+    }
+}
+
+/// Minimum time between two progress callbacks
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Turns ffmpeg `-progress` key=value blocks into throttled [`ProgressUpdate`]s
+struct ProgressTracker<'a> {
+    total_seconds: Option<f64>,
+    last_report: Option<Instant>,
+    /// The block being read; ffmpeg ends each one with a `progress=` line
+    current: ProgressUpdate,
+    callback: &'a mut dyn FnMut(ProgressUpdate),
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(total_seconds: Option<f64>, callback: &'a mut dyn FnMut(ProgressUpdate)) -> Self {
+        Self {
+            total_seconds,
+            last_report: None,
+            current: ProgressUpdate::default(),
+            callback,
+        }
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        let Some((key, value)) = line.split_once('=') else {
+            return;
+        };
+        let value = value.trim();
+        match key {
+            // Despite its name, out_time_ms is also reported in microseconds
+            "out_time_us" | "out_time_ms" => {
+                if let Some(time) = value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|micros| Duration::try_from_secs_f64(micros / 1_000_000.0).ok())
+                {
+                    self.current.time = time;
+                }
+            }
+            "fps" => self.current.fps = value.parse().ok(),
+            "speed" => self.current.speed = value.trim_end_matches('x').parse().ok(),
+            "progress" => {
+                let end = value == "end";
+                let due = self
+                    .last_report
+                    .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL);
+                if !end && !due {
+                    return;
+                }
+                self.last_report = Some(Instant::now());
+                let fraction = if end {
+                    Some(1.0)
+                } else {
+                    self.total_seconds
+                        .filter(|total| *total > 0.0)
+                        .map(|total| (self.current.time.as_secs_f64() / total).clamp(0.0, 1.0))
+                };
+                (self.callback)(ProgressUpdate {
+                    fraction,
+                    ..self.current
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adapts a completion-fraction callback to [`ProgressUpdate`]s, dropping those
+/// without a fraction
+fn fraction_reporter(progress: &mut dyn FnMut(f64)) -> impl FnMut(ProgressUpdate) + '_ {
+    move |update| {
+        if let Some(fraction) = update.fraction {
+            progress(fraction)
+        }
+    }
+}
+
+/// Selects which streams of the input get reversed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReverseMode {
+    /// Reverse both video and audio
+    #[default]
+    Both,
+    /// Reverse the video and copy the audio through unchanged.
+    ///
+    /// The forward audio is copied as-is, so the audio and video tracks may
+    /// end up with slightly different lengths; this is not treated as an error.
+    VideoOnly,
+    /// Reverse the audio and copy the video through unchanged
+    AudioOnly,
+}
+
+impl ReverseMode {
+    fn reverses_video(self) -> bool {
+        self != ReverseMode::AudioOnly
+    }
+
+    fn reverses_audio(self) -> bool {
+        self != ReverseMode::VideoOnly
+    }
+}
+
+/// What to do when the output file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverwritePolicy {
+    /// Replace the existing file
+    #[default]
+    Always,
+    /// Fail with [`VideoError::OutputExists`]
+    Never,
+    /// Leave the existing file alone and return its path without running ffmpeg
+    Skip,
+}
+
+impl OverwritePolicy {
+    /// The ffmpeg flag that matches the policy
+    fn ffmpeg_flag(self) -> &'static str {
+        match self {
+            OverwritePolicy::Always => "-y",
+            OverwritePolicy::Never | OverwritePolicy::Skip => "-n",
+        }
+    }
+
+    /// Parses the lowercase policy name used by [`OVERWRITE_ENV`]
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "always" => Some(OverwritePolicy::Always),
+            "never" => Some(OverwritePolicy::Never),
+            "skip" => Some(OverwritePolicy::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// How audio that keeps playing forward is fitted to reversed video of another length,
+/// as a [`speed`](VideoReverserBuilder::speed) change or
+/// [`repeat`](VideoReverserBuilder::repeat) gives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioFit {
+    /// End the output with the shorter stream (`-shortest`), copying the audio as is
+    #[default]
+    Shortest,
+    /// Pad the audio with silence up to the video's length (`apad`), so the whole video
+    /// plays; audio running longer than the video is cut
+    Pad,
+}
+
+/// How the audio track is treated.
+///
+/// Filters run in the order `areverse`, `atempo` (for a speed change), `volume`,
+/// `loudnorm`. When the audio is neither reversed nor filtered nor resampled it is
+/// copied through unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioOptions {
+    /// Reverse the audio with `areverse` (ignored in [`ReverseMode::VideoOnly`])
+    pub reverse: bool,
+    /// Remove the audio track from the output entirely
+    pub drop: bool,
+    /// Volume multiplier applied after reversing, e.g. `0.5` or `2.0`
+    pub volume: Option<f64>,
+    /// Apply EBU R128 loudness normalization (`loudnorm`)
+    pub normalize: bool,
+    /// Output channel count (`-ac`)
+    pub channels: Option<u32>,
+    /// Output sample rate in Hz (`-ar`)
+    pub sample_rate: Option<u32>,
+    /// Keep the original audio playing forward, untouched by speed changes, repeats
+    /// and fades, and fit it to the reversed video like this; overrides `reverse`
+    pub keep_forward: Option<AudioFit>,
+}
+
+impl Default for AudioOptions {
+    fn default() -> Self {
+        Self {
+            reverse: true,
+            drop: false,
+            volume: None,
+            normalize: false,
+            channels: None,
+            sample_rate: None,
+            keep_forward: None,
+        }
+    }
+}
+
+impl AudioOptions {
+    /// Keeps the original audio playing forward under the reversed video, e.g. for
+    /// narration over reversed footage, fitted to the video with `fit`. Applies to plain
+    /// reversals.
+    pub fn keep_forward(fit: AudioFit) -> Self {
+        Self {
+            reverse: false,
+            keep_forward: Some(fit),
+            ..Self::default()
+        }
+    }
+
+    /// Rejects combinations that contradict each other or the reverse mode
+    fn validate(&self, mode: ReverseMode) -> Result<(), VideoError> {
+        let invalid = |msg: &str| Err(VideoError::InvalidOptions(msg.to_string()));
+        if self.drop {
+            if self.volume.is_some()
+                || self.normalize
+                || self.channels.is_some()
+                || self.sample_rate.is_some()
+                || self.keep_forward.is_some()
+            {
+                return invalid(
+                    "Audio filters and format options can't be combined with dropping the audio",
+                );
+            }
+            if mode == ReverseMode::AudioOnly {
+                return invalid("Audio-only reversal can't drop the audio track");
+            }
+        }
+        if mode == ReverseMode::AudioOnly && (!self.reverse || self.keep_forward.is_some()) {
+            return invalid("Audio-only reversal requires audio reversing to be enabled");
+        }
+        if self.volume.is_some_and(|v| !v.is_finite() || v <= 0.0) {
+            return invalid("Audio volume must be a positive number");
+        }
+        if self.channels == Some(0) || self.sample_rate == Some(0) {
+            return invalid("Audio channel count and sample rate must be positive");
+        }
+        Ok(())
+    }
+
+    /// The `-af` filter chain for `mode`, playback `speed` and `repeat` plays, empty if
+    /// the audio needs no filtering. Reversed audio also gets the `fade`.
+    fn filters(
+        &self,
+        mode: ReverseMode,
+        speed: Option<f64>,
+        repeat: u32,
+        fade: Option<Fade>,
+    ) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(fit) = self.keep_forward {
+            if fit == AudioFit::Pad {
+                filters.push("apad".to_string());
+            }
+        } else {
+            let reverse = self.reverse && mode.reverses_audio();
+            if reverse {
+                filters.push("areverse".to_string());
+            }
+            if let Some(speed) = speed {
+                filters.extend(atempo_chain(speed));
+            }
+            if repeat > 1 {
+                filters.push(repeat_filter(repeat, true));
+            }
+            if let Some(fade) = fade.filter(|_| reverse) {
+                filters.push(fade.filters("afade"));
+            }
+        }
+        if let Some(volume) = self.volume {
+            filters.push(format!("volume={volume}"));
+        }
+        if self.normalize {
+            filters.push("loudnorm".to_string());
+        }
+        filters
+    }
+
+    /// Whether the audio stream has to be re-encoded
+    fn needs_encode(&self, mode: ReverseMode, speed: Option<f64>, repeat: u32) -> bool {
+        !self.drop
+            && (!self.filters(mode, speed, repeat, None).is_empty()
+                || self.channels.is_some()
+                || self.sample_rate.is_some())
+    }
+
+    /// The audio arguments for `mode`, playback `speed`, `repeat` plays and `fade`
+    fn ffmpeg_args(
+        &self,
+        mode: ReverseMode,
+        speed: Option<f64>,
+        repeat: u32,
+        fade: Option<Fade>,
+    ) -> Vec<OsString> {
+        if self.drop {
+            return vec!["-an".into()];
+        }
+        let mut args: Vec<OsString> = Vec::new();
+        if !self.needs_encode(mode, speed, repeat) {
+            args.extend(["-c:a".into(), "copy".into()]);
+        } else {
+            let filters = self.filters(mode, speed, repeat, fade);
+            if !filters.is_empty() {
+                args.extend(["-af".into(), filters.join(",").into()]);
+            }
+            if let Some(channels) = self.channels {
+                args.extend(["-ac".into(), channels.to_string().into()]);
+            }
+            if let Some(sample_rate) = self.sample_rate {
+                args.extend(["-ar".into(), sample_rate.to_string().into()]);
+            }
+        }
+        // Padded audio never ends on its own
+        if self.keep_forward.is_some() {
+            args.push("-shortest".into());
+        }
+        args
+    }
+}
+
+/// A filter that plays its input `count` times back to back, within a `-vf` chain or,
+/// with `audio` set, an `-af` chain.
+///
+/// The input is split into one copy per play; every copy but the one playing is queued
+/// in memory until concat reaches it.
+fn repeat_filter(count: u32, audio: bool) -> String {
+    let labels: String = (0..count).map(|i| format!("[r{i}]")).collect();
+    let (split, video, audio) = if audio {
+        ("asplit", 0, 1)
+    } else {
+        ("split", 1, 0)
+    };
+    format!("{split}={count}{labels};{labels}concat=n={count}:v={video}:a={audio}")
+}
+
+/// `atempo` filters that together change the audio speed by `speed`.
+///
+/// A single `atempo` only accepts factors from 0.5 to 2.0, so larger changes are split
+/// into a chain: 4.0 becomes `atempo=2,atempo=2` and 0.3 becomes `atempo=0.5,atempo=0.6`.
+fn atempo_chain(speed: f64) -> Vec<String> {
+    let mut filters = Vec::new();
+    let mut remaining = speed;
+    while remaining > 2.0 {
+        filters.push("atempo=2".to_string());
+        remaining /= 2.0;
+    }
+    while remaining < 0.5 {
+        filters.push("atempo=0.5".to_string());
+        remaining /= 0.5;
+    }
+    if remaining != 1.0 || filters.is_empty() {
+        filters.push(format!("atempo={remaining}"));
+    }
+    filters
+}
+
+/// Environment variable consulted for the ffmpeg binary when no path is configured
+pub const FFMPEG_PATH_ENV: &str = "MDMP4REV_FFMPEG";
+
+/// Environment variable consulted for the ffprobe binary when no path is configured
+pub const FFPROBE_PATH_ENV: &str = "MDMP4REV_FFPROBE";
+
+/// Environment variable giving the default [`VideoReverserBuilder::crf`]
+pub const CRF_ENV: &str = "MDMP4REV_CRF";
+
+/// Environment variable giving the default [`VideoReverserBuilder::preset`]
+pub const PRESET_ENV: &str = "MDMP4REV_PRESET";
+
+/// Environment variable giving the default overwrite policy: `always`, `never` or `skip`
+pub const OVERWRITE_ENV: &str = "MDMP4REV_OVERWRITE";
+
+/// Environment variable giving the accepted input extensions, comma separated
+pub const EXTENSIONS_ENV: &str = "MDMP4REV_EXTENSIONS";
+
+/// Container extensions accepted by default
+pub const DEFAULT_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "webm", "avi"];
+
+/// Placeholders accepted by [`VideoReverserBuilder::output_template`]
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &["stem", "ext", "parent", "date", "index", "duration"];
+
+/// Pixel format H.264 outputs are converted to when the source's won't play everywhere
+const COMPATIBLE_PIXEL_FORMAT: &str = "yuv420p";
+
+/// Source pixel formats that browsers and QuickTime play once encoded to H.264
+const PLAYABLE_PIXEL_FORMATS: &[&str] = &["yuv420p", "yuvj420p", "nv12"];
+
+/// Containers accepted by [`VideoReverserBuilder::output_format`]
+pub const OUTPUT_FORMATS: &[&str] = &["mp4", "mov", "mkv", "webm", "avi"];
+
+/// Video encoders whose output WebM can hold
+const WEBM_ENCODERS: &[&str] = &[
+    "libvpx",
+    "libvpx-vp9",
+    "libaom-av1",
+    "libsvtav1",
+    "librav1e",
+];
+
+/// CRF that x264 and x265 encode at by default, the baseline of size estimates
+const DEFAULT_CRF: u8 = 23;
+
+/// CRF for VP9 when none is configured, about the quality of x264's default
+const DEFAULT_VP9_CRF: u8 = 31;
+
+/// Encoders for an output container whose ffmpeg defaults give poor results, used when
+/// no [`encoder`](VideoReverserBuilder::encoder) is configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ContainerDefaults {
+    video_encoder: Option<&'static str>,
+    /// Used whenever the audio is re-encoded rather than copied
+    audio_encoder: Option<&'static str>,
+    /// The video encoder ffmpeg picks itself when none is given, if known, which decides
+    /// the encoder-specific options that apply
+    implied_video_encoder: Option<&'static str>,
+}
+
+impl ContainerDefaults {
+    /// The defaults for the muxer `format`, or for the extension of `output` if the
+    /// muxer isn't named
+    fn for_output(format: Option<&str>, output: &Path) -> Self {
+        let container = format
+            .map(str::to_ascii_lowercase)
+            .or_else(|| Some(output.extension()?.to_string_lossy().to_ascii_lowercase()));
+        match container.as_deref() {
+            // Left to itself ffmpeg may pick VP8; Opus is what browsers expect next to VP9
+            Some("webm") => Self {
+                video_encoder: Some("libvpx-vp9"),
+                audio_encoder: Some("libopus"),
+                implied_video_encoder: None,
+            },
+            Some("mp4" | "m4v" | "mov" | "mkv" | "matroska") => Self {
+                implied_video_encoder: Some("libx264"),
+                ..Self::default()
+            },
+            Some("avi") => Self {
+                implied_video_encoder: Some("mpeg4"),
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Highest CRF accepted by x264 and x265 (lower is better quality)
+pub const MAX_CRF: u8 = 51;
+
+/// x264/x265 presets, fastest first
+pub const PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+/// ffmpeg muxers that can be written to a pipe without seeking back.
+///
+/// MP4 and MOV write their index after the media data, so they aren't included.
+pub const STREAMABLE_FORMATS: &[&str] = &["matroska", "webm", "mpegts", "nut"];
+
+/// Reverses videos by running ffmpeg with the configured options.
+///
+/// Cloning is cheap and shares the command runner, so a configured reverser can be
+/// handed to each worker of a thread pool.
+#[derive(Clone)]
+pub struct VideoReverser {
+    runner: Arc<dyn CommandRunner>,
+    ffmpeg_path: PathBuf,
+    ffprobe_path: PathBuf,
+    /// What [`supported_output_formats`](Self::supported_output_formats) found
+    output_formats: OnceLock<Vec<String>>,
+    /// Warnings from the first ffmpeg check that passed; later checks are skipped
+    ffmpeg_checked: OnceLock<Vec<Warning>>,
+    allowed_extensions: Vec<String>,
+    dry_run: bool,
+    match_source_encoding: bool,
+    recursive: bool,
+    probe_validation: bool,
+    mode: ReverseMode,
+    overwrite: OverwritePolicy,
+    timeout: Option<Duration>,
+    retries: u32,
+    retry_delay: Duration,
+    cancel: Option<Arc<AtomicBool>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    min_ffmpeg_version: Option<(u32, u32)>,
+    strict_ffmpeg_check: bool,
+    video_stream: Option<usize>,
+    audio_stream: Option<usize>,
+    audio: AudioOptions,
+    encoder: Option<String>,
+    crf: Option<u8>,
+    preset: Option<String>,
+    target_bitrate: Option<u32>,
+    two_pass: bool,
+    threads: Option<u32>,
+    accurate_seek: bool,
+    preserve_metadata: bool,
+    verify_output: bool,
+    drop_non_av_streams: bool,
+    allow_in_place: bool,
+    extra_args: Vec<String>,
+    video_filters: Vec<String>,
+    speed: Option<f64>,
+    fade: Option<Duration>,
+    repeat: u32,
+    normalize_fps: Option<f64>,
+    aspect_crop: Option<(u32, u32)>,
+    pixel_format: Option<String>,
+    temp_dir: Option<PathBuf>,
+    auto_rotate: bool,
+    quiet: bool,
+    verbose_ffmpeg: bool,
+    output_format: Option<String>,
+    output_template: Option<String>,
+    output_dir: Option<PathBuf>,
+    suffix: String,
+    prefix: bool,
+}
+
+impl Default for VideoReverser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configures a [`VideoReverser`]; obtained from [`VideoReverser::builder`]
+#[derive(Clone)]
+pub struct VideoReverserBuilder {
+    reverser: VideoReverser,
+}
+
+impl Default for VideoReverserBuilder {
+    fn default() -> Self {
+        Self {
+            reverser: VideoReverser {
+                runner: Arc::new(RealCommandRunner),
+                ffmpeg_path: default_tool_path(std::env::var_os(FFMPEG_PATH_ENV), "ffmpeg"),
+                ffprobe_path: default_tool_path(std::env::var_os(FFPROBE_PATH_ENV), "ffprobe"),
+                output_formats: OnceLock::new(),
+                ffmpeg_checked: OnceLock::new(),
+                allowed_extensions: DEFAULT_EXTENSIONS.iter().map(|s| s.to_string()).collect(),
+                dry_run: false,
+                match_source_encoding: false,
+                recursive: false,
+                probe_validation: true,
+                mode: ReverseMode::default(),
+                overwrite: OverwritePolicy::default(),
+                timeout: None,
+                retries: 0,
+                retry_delay: Duration::from_secs(1),
+                cancel: None,
+                metrics: None,
+                min_ffmpeg_version: None,
+                strict_ffmpeg_check: false,
+                video_stream: None,
+                audio_stream: None,
+                audio: AudioOptions::default(),
+                encoder: None,
+                crf: None,
+                preset: None,
+                target_bitrate: None,
+                two_pass: false,
+                threads: None,
+                accurate_seek: true,
+                preserve_metadata: true,
+                verify_output: false,
+                drop_non_av_streams: true,
+                allow_in_place: false,
+                extra_args: Vec::new(),
+                video_filters: Vec::new(),
+                speed: None,
+                fade: None,
+                repeat: 1,
+                normalize_fps: None,
+                aspect_crop: None,
+                pixel_format: None,
+                temp_dir: None,
+                auto_rotate: true,
+                quiet: false,
+                verbose_ffmpeg: false,
+                output_format: None,
+                output_template: None,
+                output_dir: None,
+                suffix: "-rev".to_string(),
+                prefix: false,
+            },
+        }
+        .env_defaults(|name| std::env::var_os(name))
+    }
+}
+
+impl VideoReverserBuilder {
+    /// Applies the option defaults from [`CRF_ENV`], [`PRESET_ENV`], [`OVERWRITE_ENV`] and
+    /// [`EXTENSIONS_ENV`] as found by `lookup`. Values that don't parse are ignored with a
+    /// warning.
+    fn env_defaults(mut self, lookup: impl Fn(&str) -> Option<OsString>) -> Self {
+        let var = |name: &str| {
+            let value = lookup(name)?;
+            let value = value.to_string_lossy().trim().to_string();
+            (!value.is_empty()).then_some(value)
+        };
+        let ignored = |name: &str, value: &str, expected: &str| {
+            log::warn!("Ignoring {name}={value:?}: expected {expected}");
+        };
+        if let Some(value) = var(CRF_ENV) {
+            match value.parse::<u8>() {
+                Ok(crf) if crf <= MAX_CRF => self.reverser.crf = Some(crf),
+                _ => ignored(CRF_ENV, &value, &format!("a number from 0 to {MAX_CRF}")),
+            }
+        }
+        if let Some(value) = var(PRESET_ENV) {
+            self.reverser.preset = Some(value);
+        }
+        if let Some(value) = var(OVERWRITE_ENV) {
+            match OverwritePolicy::from_name(&value.to_lowercase()) {
+                Some(policy) => self.reverser.overwrite = policy,
+                None => ignored(OVERWRITE_ENV, &value, "always, never or skip"),
+            }
+        }
+        if let Some(value) = var(EXTENSIONS_ENV) {
+            let extensions: Vec<String> = value
+                .split(',')
+                .map(|ext| ext.trim().trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+            if extensions.is_empty() {
+                ignored(EXTENSIONS_ENV, &value, "comma-separated extensions");
+            } else {
+                self.reverser.allowed_extensions = extensions;
+            }
+        }
+        self
+    }
+
+    /// Runs commands through `runner` instead of spawning real processes
+    pub fn runner(mut self, runner: Box<dyn CommandRunner>) -> Self {
+        self.reverser.runner = Arc::from(runner);
+        self
+    }
+
+    /// Uses the ffmpeg binary at `path` instead of looking it up on `PATH`
+    pub fn ffmpeg_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.reverser.ffmpeg_path = path.into();
+        self
+    }
+
+    /// Uses the ffprobe binary at `path` instead of looking it up on `PATH`
+    pub fn ffprobe_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.reverser.ffprobe_path = path.into();
+        self
+    }
+
+    /// Replaces the set of input extensions (without the leading dot) that are accepted;
+    /// they are compared ignoring ASCII case
+    pub fn allowed_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.reverser.allowed_extensions = extensions;
+        self
+    }
+
+    /// Selects which streams the reverse methods reverse by default
+    pub fn mode(mut self, mode: ReverseMode) -> Self {
+        self.reverser.mode = mode;
+        self
+    }
+
+    /// Chooses what happens when the output file already exists
+    pub fn overwrite(mut self, policy: OverwritePolicy) -> Self {
+        self.reverser.overwrite = policy;
+        self
+    }
+
+    /// Kills ffmpeg and fails with [`VideoError::Timeout`] if a reversal takes longer than
+    /// `timeout`. The partial output is removed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.reverser.timeout = Some(timeout);
+        self
+    }
+
+    /// Runs ffmpeg up to `retries` more times when it fails for no recognisable reason,
+    /// such as a resource shortage on a busy machine.
+    ///
+    /// Failures blamed on the input, a missing codec or the output location are not
+    /// retried, nor are timeouts and cancellations.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.reverser.retries = retries;
+        self
+    }
+
+    /// Waits `delay` before the first retry, doubling the wait for each one after it.
+    /// Defaults to one second.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.reverser.retry_delay = delay;
+        self
+    }
+
+    /// Reports [`ReverseMetrics`] to `sink` at the end of every reversal, failed ones and
+    /// each file of a batch included.
+    pub fn metrics(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.reverser.metrics = Some(Arc::new(sink));
+        self
+    }
+
+    /// Lets another thread stop a running reversal by setting `cancel` to `true`.
+    ///
+    /// ffmpeg is killed within [`RunControl::CANCEL_POLL_INTERVAL`] and the call fails with
+    /// [`VideoError::Cancelled`]; the partial output is removed. The flag isn't reset, so
+    /// later calls on the same reverser are cancelled too until it is cleared. Directory
+    /// runs start no more inputs: the next one fails with [`VideoError::Cancelled`]
+    /// without running anything and the rest are left out of the results.
+    pub fn cancel_token(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.reverser.cancel = Some(cancel);
+        self
+    }
+
+    /// Fails reversals with [`VideoError::FFmpegTooOld`] if the installed ffmpeg is older
+    /// than `major.minor`
+    pub fn min_ffmpeg_version(mut self, major: u32, minor: u32) -> Self {
+        self.reverser.min_ffmpeg_version = Some((major, minor));
+        self
+    }
+
+    /// Fails reversals with [`VideoError::NotFFmpeg`] unless `-version` identifies the
+    /// configured binary as ffmpeg, catching avconv symlinks and wrapper scripts whose
+    /// options differ. Off by default.
+    pub fn strict_ffmpeg_check(mut self, enabled: bool) -> Self {
+        self.reverser.strict_ffmpeg_check = enabled;
+        self
+    }
+
+    /// Configures how the audio track is reversed, filtered or dropped
+    pub fn audio_options(mut self, audio: AudioOptions) -> Self {
+        self.reverser.audio = audio;
+        self
+    }
+
+    /// Encodes the reversed video with `encoder` (e.g. `h264_nvenc` or `hevc_videotoolbox`),
+    /// adding the matching `-hwaccel` decode flag for NVENC, VideoToolbox and QSV.
+    ///
+    /// The `reverse` filter itself always runs on the CPU; only decoding and encoding are
+    /// offloaded. Reversals fail with [`VideoError::InvalidInput`] if `ffmpeg -encoders`
+    /// doesn't list the encoder. Ignored in [`ReverseMode::AudioOnly`], where the video is copied.
+    ///
+    /// WebM outputs default to `libvpx-vp9`, with Opus audio whenever the audio is re-encoded.
+    pub fn encoder(mut self, encoder: impl Into<String>) -> Self {
+        self.reverser.encoder = Some(encoder.into());
+        self
+    }
+
+    /// Sets the x264/x265 constant rate factor, from 0 (lossless) to [`MAX_CRF`].
+    ///
+    /// Also applies to libvpx-vp9, which otherwise encodes at CRF 31 with `-b:v 0`. Ignored
+    /// with a warning when [`encoder`](Self::encoder) selects another encoder.
+    pub fn crf(mut self, crf: u8) -> Self {
+        self.reverser.crf = Some(crf);
+        self
+    }
+
+    /// Encodes the video at an average bitrate of `kbps` kilobits per second with
+    /// `-b:v`, for outputs that must fit a size limit. Can't be combined with a CRF.
+    pub fn target_bitrate(mut self, kbps: u32) -> Self {
+        self.reverser.target_bitrate = Some(kbps);
+        self
+    }
+
+    /// Hits the [`target_bitrate`](Self::target_bitrate) more precisely by running ffmpeg
+    /// twice: a first pass that only analyses the reversed video, then the real encode.
+    ///
+    /// Takes about twice as long. The statistics go to uniquely named files in the system
+    /// temp directory, so parallel runs don't collide, and are removed afterwards. Applies
+    /// to plain reversals that re-encode the video; progress is only reported for the
+    /// second pass. [`VideoReverser::reverse_video_async`] always encodes in one pass.
+    pub fn two_pass(mut self, enabled: bool) -> Self {
+        self.reverser.two_pass = enabled;
+        self
+    }
+
+    /// Limits ffmpeg to `count` threads (`-threads`, and `-x264-params threads=` when
+    /// encoding with x264, which ffmpeg picks for MP4, MOV and MKV), so one reversal
+    /// doesn't take every core of a shared machine. Must be at least 1.
+    ///
+    /// When unset, ffmpeg picks its own thread count, except in
+    /// [`reverse_directory_parallel`](VideoReverser::reverse_directory_parallel), which
+    /// divides the available cores between the concurrent runs.
+    pub fn threads(mut self, count: u32) -> Self {
+        self.reverser.threads = Some(count);
+        self
+    }
+
+    /// Sets the x264/x265 speed preset, one of [`PRESETS`].
+    ///
+    /// Ignored with a warning when [`encoder`](Self::encoder) selects another encoder.
+    pub fn preset(mut self, preset: impl Into<String>) -> Self {
+        self.reverser.preset = Some(preset.into());
+        self
+    }
+
+    /// Controls how segment reversals seek to their start (on by default).
+    ///
+    /// Accurate seeking places `-ss`/`-to` after `-i`, so ffmpeg decodes from the start of
+    /// the file and the segment contains exactly the requested frames; this gets slower the
+    /// later the segment starts. Disabling it seeks on the input instead, which is fast but
+    /// may snap to the nearest keyframe and include extra frames.
+    pub fn accurate_seek(mut self, accurate: bool) -> Self {
+        self.reverser.accurate_seek = accurate;
+        self
+    }
+
+    /// Copies the source's container tags (title, artist, comment, creation time, ...) to
+    /// the output with `-map_metadata 0` (on by default).
+    ///
+    /// Stream timing such as the duration is always regenerated. Chapters are copied by
+    /// ffmpeg regardless but keep their forward timestamps. Rotation needs no adjustment:
+    /// ffmpeg applies it while decoding, and reversing doesn't change orientation.
+    pub fn preserve_metadata(mut self, enabled: bool) -> Self {
+        self.reverser.preserve_metadata = enabled;
+        self
+    }
+
+    /// Leaves subtitle and data streams out of the output with `-sn -dn` (on by default),
+    /// since players often choke on them once the video is reversed.
+    ///
+    /// No `-map 0:v -map 0:a?` is added: ffmpeg's own stream choice already keeps one
+    /// video and one audio stream, whereas mapping them all would also reverse, and hold in
+    /// memory, every extra video stream such as cover art or a second camera angle. Use
+    /// [`video_stream`](Self::video_stream) and [`audio_stream`](Self::audio_stream) to
+    /// pick others.
+    ///
+    /// When disabled, the subtitle stream ffmpeg selects is copied with `-c:s copy`. Its cues
+    /// keep their forward timing, so they no longer match what is on screen; the container
+    /// must also support the subtitle codec.
+    pub fn drop_non_av_streams(mut self, enabled: bool) -> Self {
+        self.reverser.drop_non_av_streams = enabled;
+        self
+    }
+
+    /// Decodes the whole output after ffmpeg finishes and fails with
+    /// [`VideoError::OutputCorrupt`] if anything went wrong, before the output replaces an
+    /// existing file. Roughly doubles the running time; off by default.
+    pub fn verify_output(mut self, enabled: bool) -> Self {
+        self.reverser.verify_output = enabled;
+        self
+    }
+
+    /// Allows the output to be the input file itself, replacing it with the reversed video.
+    ///
+    /// Off by default: an output that resolves to the input (via symlinks, `..` or a hard
+    /// link) is rejected with [`VideoError::InvalidInput`]. When allowed, the replacement is
+    /// still atomic, since ffmpeg writes to a temporary file first.
+    pub fn allow_in_place(mut self, allowed: bool) -> Self {
+        self.reverser.allow_in_place = allowed;
+        self
+    }
+
+    /// Changes the playback speed of the reversed clip: below 1.0 slows it down (0.5 is
+    /// half speed), above 1.0 speeds it up. Must be positive.
+    ///
+    /// The video is retimed with `setpts` and the audio with `atempo`, chained for
+    /// factors outside the 0.5-2.0 range a single `atempo` accepts. A speed change
+    /// always re-encodes the video, even in [`ReverseMode::AudioOnly`].
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.reverser.speed = Some(speed);
+        self
+    }
+
+    /// Fades the reversed video in from black and out to black over `length` at each end,
+    /// and the reversed audio in and out of silence. Must be less than half the output's
+    /// length.
+    ///
+    /// The fade-out is placed from the duration ffprobe reports, so piped input can't be
+    /// faded. Streams that are copied rather than reversed are left alone. Applies to
+    /// plain reversals, GIFs and boomerangs, where the boomerang is faded as a whole.
+    pub fn fade(mut self, length: Duration) -> Self {
+        self.reverser.fade = Some(length);
+        self
+    }
+
+    /// Plays the reversed clip `count` times back to back in one file, audio included,
+    /// for seamless loops. Must be at least 1, the default.
+    ///
+    /// The reversed clip is already held in memory while it is encoded, and every extra
+    /// play queues another copy of it, so memory use and encoding time grow with the
+    /// count; keep large counts to short clips. Repeating always re-encodes both
+    /// streams, even ones a [`ReverseMode`] would copy, except audio kept playing
+    /// forward with [`AudioOptions::keep_forward`]. Applies to plain reversals.
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.reverser.repeat = count;
+        self
+    }
+
+    /// Resamples the video to a constant `fps` frames per second before reversing it.
+    ///
+    /// The reverse filter plays frames back at evenly spaced times, so video with a
+    /// variable frame rate, as screen recorders write, comes out stuttering. Inputs that
+    /// look like that get a [`Warning::VariableFrameRate`] while this is unset. Must be
+    /// positive; applies to plain reversals that reverse the video.
+    pub fn normalize_fps(mut self, fps: f64) -> Self {
+        self.reverser.normalize_fps = Some(fps);
+        self
+    }
+
+    /// Crops the video to the aspect ratio `width:height`, e.g. `(9, 16)` for vertical
+    /// video platforms, keeping the centre of the picture.
+    ///
+    /// The crop window is computed from the input's dimensions as ffprobe reports them,
+    /// after any [`auto_rotate`](Self::auto_rotate), and rounded down to even sizes. It
+    /// is applied before `reverse`, so less video is held in memory. Both parts must be
+    /// positive; reversals fail with [`VideoError::InvalidInput`] when the input's size
+    /// is unknown or too small for the ratio. Applies to plain reversals that reverse the
+    /// video.
+    pub fn aspect_crop(mut self, width: u32, height: u32) -> Self {
+        self.reverser.aspect_crop = Some((width, height));
+        self
+    }
+
+    /// Encodes the video in this pixel format (`-pix_fmt`), e.g. `yuv420p`.
+    ///
+    /// When unset, ffmpeg keeps the source's format, except that an H.264 output in an
+    /// MP4 or MOV is converted to `yuv420p` with a [`Warning::PixelFormatConverted`] if
+    /// the source's format, such as `yuv444p`, won't play in browsers and QuickTime.
+    /// Applies whenever the video is re-encoded.
+    pub fn pixel_format(mut self, format: impl Into<String>) -> Self {
+        self.reverser.pixel_format = Some(format.into());
+        self
+    }
+
+    /// Puts temporary files in `dir`, which must exist and be writable: the output
+    /// while ffmpeg writes it, two-pass statistics and the scratch files of
+    /// [`reverse_to_bytes`](VideoReverser::reverse_to_bytes) and scene reversal.
+    ///
+    /// By default the output is written next to its final path, so it can be renamed
+    /// into place, and other scratch files go to the system temp directory. A `dir` on
+    /// another file system costs a copy of the finished output.
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.reverser.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Keeps ffmpeg to reporting errors by passing `-hide_banner -loglevel error`.
+    ///
+    /// ffmpeg's output is always captured rather than shown, and its last meaningful
+    /// line ends up in [`VideoError::ProcessingError`]; this just keeps the captured log
+    /// short. Can't be combined with [`verbose_ffmpeg`](Self::verbose_ffmpeg).
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.reverser.quiet = quiet;
+        self
+    }
+
+    /// Passes `-loglevel verbose` to ffmpeg and copies its log to stderr as it runs,
+    /// for debugging. The log is still captured for error messages.
+    ///
+    /// Piped reversals and [`VideoReverser::reverse_video_async`] get the log level
+    /// but don't copy the log.
+    pub fn verbose_ffmpeg(mut self, verbose: bool) -> Self {
+        self.reverser.verbose_ffmpeg = verbose;
+        self
+    }
+
+    /// Turns re-encoded video upright according to the input's rotation metadata, as
+    /// phones record it, and clears the metadata so players don't turn it again. On by
+    /// default.
+    ///
+    /// The rotation is read while validating the input, so nothing is corrected with
+    /// [`probe_validation`](Self::probe_validation) off. Video that is copied keeps its
+    /// metadata and needs no correction.
+    pub fn auto_rotate(mut self, enabled: bool) -> Self {
+        self.reverser.auto_rotate = enabled;
+        self
+    }
+
+    /// Reverses the video stream at `index` of the file (see
+    /// [`VideoReverser::list_streams`]) instead of the first one; the other video
+    /// streams are left out of the output.
+    ///
+    /// Applies to plain reversals; GIFs, comparisons and boomerangs use the first streams.
+    pub fn video_stream(mut self, index: usize) -> Self {
+        self.reverser.video_stream = Some(index);
+        self
+    }
+
+    /// Takes the audio from the stream at `index` instead of the first audio stream,
+    /// like [`video_stream`](Self::video_stream)
+    pub fn audio_stream(mut self, index: usize) -> Self {
+        self.reverser.audio_stream = Some(index);
+        self
+    }
+
+    /// Writes generated outputs with this extension, e.g. `"mp4"` to turn `clip.mkv` into
+    /// `clip-rev.mp4`; ffmpeg picks the container from it. Must be one of
+    /// [`OUTPUT_FORMATS`], and WebM only holds VP8, VP9 or AV1 video.
+    ///
+    /// WebM outputs are encoded with VP9 at constant quality and Opus audio unless
+    /// configured otherwise. VP9 encodes best in two passes: for a size-limited WebM,
+    /// combine [`target_bitrate`](Self::target_bitrate) with [`two_pass`](Self::two_pass).
+    pub fn output_format(mut self, extension: impl Into<String>) -> Self {
+        self.reverser.output_format = Some(extension.into());
+        self
+    }
+
+    /// Names generated outputs from a template such as `"{stem}_rev_{date}.{ext}"`, in
+    /// place of the suffix or prefix. The placeholders are [`TEMPLATE_PLACEHOLDERS`]:
+    ///
+    /// - `{stem}`: the input name without its extension
+    /// - `{ext}`: the input extension, or the [`output_format`](Self::output_format)
+    /// - `{parent}`: the name of the input's directory
+    /// - `{date}`: today's date (UTC) as `YYYY-MM-DD`
+    /// - `{index}`: the 1-based position of the input in a batch; 1 for single files
+    /// - `{duration}`: the input length in whole seconds, or `unknown` without ffprobe
+    ///
+    /// Unknown placeholders fail the reversal with [`VideoError::InvalidInput`]. Path
+    /// separators in the result are replaced with `_`, so the output always lands next to
+    /// the input or in the [`output_dir`](Self::output_dir).
+    ///
+    /// Directory runs take files whose name fits the template for earlier outputs and
+    /// skip them. A template made of only `{stem}`, `{parent}` and `{ext}` fits every
+    /// name, so its outputs should go to an output directory instead.
+    pub fn output_template(mut self, template: impl Into<String>) -> Self {
+        self.reverser.output_template = Some(template.into());
+        self
+    }
+
+    /// Writes generated outputs to `dir` instead of next to their inputs, creating it
+    /// as needed.
+    ///
+    /// Directory runs recreate the input's subdirectories under `dir`, so a
+    /// [`recursive`](Self::recursive) batch over `clips` writes `clips/a/b.mp4` to
+    /// `dir/a/b-rev.mp4` and leaves the source tree untouched. A `dir` inside the input
+    /// directory isn't descended into.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.reverser.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Replaces the "-rev" text added to generated output names, e.g. `"_reversed"`
+    pub fn suffix(mut self, text: impl Into<String>) -> Self {
+        self.reverser.suffix = text.into();
+        self
+    }
+
+    /// Puts the [`suffix`](Self::suffix) text in front of the file stem instead of after it,
+    /// e.g. `rev-clip.mp4` with the text `"rev-"`
+    pub fn prefix(mut self, enabled: bool) -> Self {
+        self.reverser.prefix = enabled;
+        self
+    }
+
+    /// Appends arguments to every ffmpeg invocation, for flags this crate has no option for
+    /// (e.g. `-movflags +faststart` or `-pix_fmt yuv420p`).
+    ///
+    /// They land after the filters and codec settings, just before the output path, so
+    /// they can override earlier options. `-i` is rejected, since the input is managed here.
+    pub fn extra_args(mut self, args: Vec<String>) -> Self {
+        self.reverser.extra_args = args;
+        self
+    }
+
+    /// Appends ffmpeg video filters such as `eq=contrast=1.2` or `lut3d=grade.cube` to
+    /// the filter chain, to colour grade or otherwise process the video in the same pass.
+    ///
+    /// They run in order right after `reverse`, so they see the reversed frames before
+    /// any [`speed`](Self::speed) change, [`repeat`](Self::repeat) or
+    /// [`fade`](Self::fade), and the fade goes to true black. Entries can't contain `;`,
+    /// `[` or `]`, which would split the filtergraph. Applies to plain reversals that
+    /// reverse the video; in [`ReverseMode::AudioOnly`] the video is copied untouched.
+    pub fn video_filters(mut self, filters: Vec<String>) -> Self {
+        self.reverser.video_filters = filters;
+        self
+    }
+
+    /// When enabled, reverse calls validate their input and return the intended output
+    /// path without running ffmpeg. Use [`VideoReverser::plan_command`] to see the command.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.reverser.dry_run = dry_run;
+        self
+    }
+
+    /// When enabled, the source's video/audio codecs and bitrates are read with ffprobe
+    /// and passed to ffmpeg so the re-encode mirrors them. If ffprobe is unavailable a
+    /// warning is printed and ffmpeg's defaults are used.
+    pub fn match_source_encoding(mut self, enabled: bool) -> Self {
+        self.reverser.match_source_encoding = enabled;
+        self
+    }
+
+    /// When enabled, [`VideoReverser::reverse_directory`] also descends into subdirectories
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.reverser.recursive = recursive;
+        self
+    }
+
+    /// Controls the ffprobe check that the input really contains a video stream (on by default).
+    ///
+    /// Disabling it saves a process spawn per file, at the cost of renamed or truncated
+    /// files only failing once ffmpeg runs.
+    pub fn probe_validation(mut self, enabled: bool) -> Self {
+        self.reverser.probe_validation = enabled;
+        self
+    }
+
+    /// Checks the configuration for options that contradict each other or are out of
+    /// range, such as a CRF together with a target bitrate, returning
+    /// [`VideoError::InvalidOptions`] for the first conflict.
+    ///
+    /// Every reverse call runs the same checks before starting ffmpeg; this lets a
+    /// caller report mistakes before any input is looked at. Conflicts with a particular
+    /// input, like a fade longer than the clip, only show up when reversing it.
+    pub fn validate(&self) -> Result<(), VideoError> {
+        self.reverser
+            .validate_options(self.reverser.mode, &mut Vec::new())
+    }
+
+    /// Finishes configuration
+    pub fn build(self) -> VideoReverser {
+        self.reverser
+    }
+}
+
+impl VideoReverser {
+    /// Creates a new VideoReverser instance with default runner and settings
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Creates a new VideoReverser with a specific runner (useful for testing)
+    pub fn new_with_runner(runner: Box<dyn CommandRunner>) -> Self {
+        Self::builder().runner(runner).build()
+    }
+
+    /// Starts configuring a VideoReverser
+    pub fn builder() -> VideoReverserBuilder {
+        VideoReverserBuilder::default()
+    }
+
+    /// Checks if ffmpeg is available on the system, is new enough, and has the configured encoder.
+    ///
+    /// Only the first successful check runs ffmpeg; later calls repeat its warnings.
+    fn check_ffmpeg(&self, warnings: &mut Vec<Warning>) -> Result<(), VideoError> {
+        if let Some(checked) = self.ffmpeg_checked.get() {
+            warnings.extend(checked.iter().cloned());
+            return Ok(());
+        }
+        let mut found = Vec::new();
+        self.run_ffmpeg_check(&mut found)?;
+        warnings.extend(self.ffmpeg_checked.get_or_init(|| found).iter().cloned());
+        Ok(())
+    }
+
+    fn run_ffmpeg_check(&self, warnings: &mut Vec<Warning>) -> Result<(), VideoError> {
+        match self
+            .runner
+            .run(self.ffmpeg_path.as_os_str(), &["-version".into()])
+        {
+            Ok(output) => {
+                self.check_version(&output, warnings)?;
+                self.check_encoder()?;
+                match &self.output_format {
+                    Some(format) => check_output_format(format, &self.supported_output_formats()?),
+                    None => Ok(()),
+                }
+            }
+            Err(_) => Err(VideoError::FFmpegNotFound),
+        }
+    }
+
+    /// Forgets the cached results of the ffmpeg checks and of
+    /// [`supported_output_formats`](Self::supported_output_formats), so the next
+    /// reverse asks ffmpeg again, e.g. after it was upgraded while the program runs.
+    pub fn clear_ffmpeg_cache(&mut self) {
+        self.ffmpeg_checked = OnceLock::new();
+        self.output_formats = OnceLock::new();
+    }
+
+    /// The entries of [`OUTPUT_FORMATS`] this ffmpeg build can write, according to
+    /// `ffmpeg -formats`.
+    ///
+    /// ffmpeg is asked once per reverser; later calls return the same list.
+    pub fn supported_output_formats(&self) -> Result<Vec<String>, VideoError> {
+        if let Some(formats) = self.output_formats.get() {
+            return Ok(formats.clone());
+        }
+        let output = self
+            .runner
+            .run(self.ffmpeg_path.as_os_str(), &formats_args())
+            .map_err(|_| VideoError::FFmpegNotFound)?;
+        let formats = muxable_output_formats(&String::from_utf8_lossy(&output.stdout));
+        Ok(self.output_formats.get_or_init(|| formats).clone())
+    }
+
+    /// Checks that ffprobe can be run.
+    ///
+    /// ffprobe is optional: input validation, source-encoding matching, progress totals and
+    /// output details each skip their probe with a warning when it is missing. Call this
+    /// first if your use depends on those probes.
+    pub fn check_ffprobe(&self) -> Result<(), VideoError> {
+        self.runner
+            .run(self.ffprobe_path.as_os_str(), &["-version".into()])
+            .map(|_| ())
+            .map_err(|_| VideoError::FFprobeNotFound)
+    }
+
+    /// The installed ffmpeg's `(major, minor)` version.
+    ///
+    /// Returns `Ok(None)` for builds whose version string has no release number,
+    /// such as git snapshots (`ffmpeg version N-112345-g...`).
+    pub fn ffmpeg_version(&self) -> Result<Option<(u32, u32)>, VideoError> {
+        let output = self
+            .runner
+            .run(self.ffmpeg_path.as_os_str(), &["-version".into()])
+            .map_err(|_| VideoError::FFmpegNotFound)?;
+        Ok(parse_ffmpeg_version(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Applies the strict identity check and the minimum version requirement to
+    /// `ffmpeg -version` output.
+    ///
+    /// A version that can't be parsed is let through with a warning, since those are
+    /// usually snapshot builds newer than any release.
+    fn check_version(
+        &self,
+        output: &std::process::Output,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(), VideoError> {
+        if self.strict_ffmpeg_check {
+            identify_ffmpeg(&String::from_utf8_lossy(&output.stdout)).map_err(|reason| {
+                VideoError::NotFFmpeg {
+                    path: self.ffmpeg_path.clone(),
+                    reason,
+                }
+            })?;
+        }
+        let Some(required) = self.min_ffmpeg_version else {
+            return Ok(());
+        };
+        match parse_ffmpeg_version(&String::from_utf8_lossy(&output.stdout)) {
+            Some(found) if found < required => Err(VideoError::FFmpegTooOld { found, required }),
+            Some(_) => Ok(()),
+            None => {
+                warn(
+                    warnings,
+                    Warning::UnknownFFmpegVersion { assumed: required },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Confirms that ffmpeg was built with the configured encoder, if any
+    fn check_encoder(&self) -> Result<(), VideoError> {
+        let Some(encoder) = &self.encoder else {
+            return Ok(());
+        };
+        let output = self
+            .runner
+            .run(self.ffmpeg_path.as_os_str(), &encoders_args())
+            .map_err(|_| VideoError::FFmpegNotFound)?;
+        check_encoder_listed(encoder, &output)
+    }
+
+    /// Length of the input as reported by ffprobe, or `None` if it can't tell
+    pub fn input_duration<P: AsRef<Path>>(&self, input_path: P) -> Option<Duration> {
+        self.probe_duration(input_path.as_ref())
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// Probes the input's duration, dimensions, frame rate and count, codecs and bitrate
+    /// with a single ffprobe run
+    pub fn probe_video_info<P: AsRef<Path>>(&self, input_path: P) -> Result<VideoInfo, VideoError> {
+        let input_path = input_path.as_ref();
+        let output = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &VideoInfo::ffprobe_args(input_path),
+            )
+            .map_err(|_| VideoError::FFprobeNotFound)?;
+        if !output.status.success() {
+            return Err(unreadable_input(input_path, &output));
+        }
+        VideoInfo::parse(&String::from_utf8_lossy(&output.stdout)).map_err(|err| {
+            VideoError::NotAVideo(format!(
+                "{}: unexpected ffprobe output ({err})",
+                input_path.display()
+            ))
+        })
+    }
+
+    /// Roughly how many bytes reversing the input with the current settings will write.
+    ///
+    /// Starts from the input's overall bitrate and duration as ffprobe reports them (the
+    /// file size if there is no bitrate), shortened by any speed change. When the video is
+    /// re-encoded with a CRF, every 6 steps away from the default 23 halve or double the
+    /// estimate. Content matters more than settings, so expect the real size to be off by
+    /// up to half in either direction; leave headroom when checking free space.
+    pub fn estimate_output_size<P: AsRef<Path>>(&self, input_path: P) -> Result<u64, VideoError> {
+        let input_path = input_path.as_ref();
+        let output = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &[
+                    "-v".into(),
+                    "error".into(),
+                    "-show_entries".into(),
+                    "format=duration,bit_rate".into(),
+                    "-of".into(),
+                    "default=noprint_wrappers=1".into(),
+                    input_path.into(),
+                ],
+            )
+            .map_err(|_| VideoError::FFprobeNotFound)?;
+        if !output.status.success() {
+            return Err(unreadable_input(input_path, &output));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let field = |name: &str| {
+            stdout
+                .lines()
+                .filter_map(|line| line.trim().split_once('='))
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value.parse::<f64>().ok())
+        };
+        let mut estimate = match (field("bit_rate"), field("duration")) {
+            (Some(bit_rate), Some(duration)) => bit_rate * duration / 8.0,
+            _ => std::fs::metadata(input_path)?.len() as f64,
+        };
+        estimate *= f64::from(self.repeat) / self.speed.unwrap_or(1.0);
+        if let Some(crf) = self.crf.filter(|_| self.encodes_video(self.mode)) {
+            estimate *= 2f64.powf((f64::from(DEFAULT_CRF) - f64::from(crf)) / 6.0);
+        }
+        Ok(estimate.round() as u64)
+    }
+
+    /// Warns about video with a variable frame rate that is going to be reversed as is
+    fn check_frame_rate(&self, job: &Job, probed: &ProbedInput, warnings: &mut Vec<Warning>) {
+        if probed.variable_frame_rate
+            && self.normalize_fps.is_none()
+            && job.effect == Effect::Reverse
+            && job.mode.reverses_video()
+        {
+            warn(warnings, Warning::VariableFrameRate);
+        }
+    }
+
+    /// Where scratch files without an output to sit next to go
+    fn scratch_dir(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Checks that files can be created in the configured temp directory, if any
+    fn check_temp_dir(&self) -> Result<(), VideoError> {
+        let Some(dir) = &self.temp_dir else {
+            return Ok(());
+        };
+        // Dropping the probe removes it again
+        let probe = TempOutput::new_in(Some(dir), Path::new("probe"));
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(probe.path())
+            .map_err(|err| unusable_temp_dir(dir, err))?;
+        Ok(())
+    }
+
+    /// Asks ffprobe for the container duration in seconds
+    fn probe_duration(&self, input_path: &Path) -> Option<f64> {
+        let output = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &duration_probe_args(input_path),
+            )
+            .ok()?;
+        parse_duration_probe(&output)
+    }
+
+    /// The crop window for `job`, if an aspect ratio is configured and the job reverses
+    /// the video, sized from the probed input
+    fn crop_for(&self, job: &Job, probed: &ProbedInput) -> Result<Option<(u32, u32)>, VideoError> {
+        let Some((ratio_width, ratio_height)) = self
+            .aspect_crop
+            .filter(|_| job.effect == Effect::Reverse && job.mode.reverses_video())
+        else {
+            return Ok(None);
+        };
+        let Some((width, height)) = probed.dimensions else {
+            return Err(VideoError::InvalidInput(format!(
+                "Can't crop {} to {ratio_width}:{ratio_height}: its dimensions are unknown",
+                job.input.display()
+            )));
+        };
+        // The crop applies to the picture after it is turned upright
+        let (width, height) = if self.auto_rotate && probed.rotation % 180 == 90 {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let (ratio_width, ratio_height) = (u64::from(ratio_width), u64::from(ratio_height));
+        let (crop_width, crop_height) =
+            if u64::from(width) * ratio_height > u64::from(height) * ratio_width {
+                (
+                    u64::from(height) * ratio_width / ratio_height,
+                    u64::from(height),
+                )
+            } else {
+                (
+                    u64::from(width),
+                    u64::from(width) * ratio_height / ratio_width,
+                )
+            };
+        // Most encoders need even dimensions for 4:2:0 video
+        let (crop_width, crop_height) = (crop_width / 2 * 2, crop_height / 2 * 2);
+        if crop_width == 0 || crop_height == 0 {
+            return Err(VideoError::InvalidInput(format!(
+                "Can't crop {} ({width}x{height}) to {ratio_width}:{ratio_height}",
+                job.input.display()
+            )));
+        }
+        // Both are at most the input's dimensions
+        Ok(Some((crop_width as u32, crop_height as u32)))
+    }
+
+    /// The fade for `job`, if one is configured and the effect takes one, checked
+    /// against the output's length. `probe` gives the input's duration in seconds
+    /// when the job has no segment.
+    fn fade_for(
+        &self,
+        job: &Job,
+        probe: impl FnOnce() -> Option<f64>,
+    ) -> Result<Option<Fade>, VideoError> {
+        let Some(length) = self.fade.map(|fade| fade.as_secs_f64()) else {
+            return Ok(None);
+        };
+        let (plays, speed) = match job.effect {
+            Effect::Reverse => (self.repeat, self.speed.unwrap_or(1.0)),
+            Effect::Gif(_) | Effect::ImageSequence(_) | Effect::AudioExtract => (1, 1.0),
+            Effect::Boomerang(boomerang) => (boomerang.loops * 2, 1.0),
+            Effect::SideBySide | Effect::Thumbnail(_) => return Ok(None),
+        };
+        let input = match job.segment {
+            Some(segment) => Some((segment.end - segment.start).as_secs_f64()),
+            None => probe(),
+        };
+        let Some(input) = input else {
+            return Err(VideoError::InvalidInput(format!(
+                "Can't fade {}: its duration is unknown",
+                job.input.display()
+            )));
+        };
+        let clip = input * f64::from(plays) / speed;
+        if length * 2.0 >= clip {
+            return Err(VideoError::InvalidOptions(format!(
+                "A {length}s fade is too long for {}, which comes out {clip}s long",
+                job.input.display()
+            )));
+        }
+        Ok(Some(Fade { length, clip }))
+    }
+
+    /// Confirms with ffprobe that the input holds at least one video stream, and an
+    /// audio stream if `mode` reverses only the audio. Returns whether there is audio
+    /// and how the video is rotated.
+    ///
+    /// If ffprobe can't be run at all the check is skipped with a warning and the input
+    /// is assumed to have audio and no rotation.
+    fn validate_input(
+        &self,
+        input_path: &Path,
+        mode: ReverseMode,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<ProbedInput, VideoError> {
+        if !self.probe_validation {
+            return Ok(ProbedInput::ASSUMED);
+        }
+        match self.runner.run(
+            self.ffprobe_path.as_os_str(),
+            &stream_probe_args(input_path),
+        ) {
+            Ok(output) => check_stream_probe(
+                input_path,
+                &output,
+                mode,
+                (self.video_stream, self.audio_stream),
+                warnings,
+            ),
+            Err(_) => {
+                warn(warnings, Warning::FFprobeUnavailable);
+                Ok(ProbedInput::ASSUMED)
+            }
+        }
+    }
+
+    /// Lists the streams of the input with ffprobe, to pick from with
+    /// [`VideoReverserBuilder::video_stream`] and [`VideoReverserBuilder::audio_stream`]
+    pub fn list_streams<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+    ) -> Result<Vec<StreamInfo>, VideoError> {
+        let input_path = input_path.as_ref();
+        let output = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &stream_probe_args(input_path),
+            )
+            .map_err(|_| VideoError::FFprobeNotFound)?;
+        if !output.status.success() {
+            return Err(unreadable_input(input_path, &output));
+        }
+        Ok(StreamInfo::parse_all(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Reads the source encoding with ffprobe, warning if it can't be determined
+    fn probe_source_encoding(
+        &self,
+        input_path: &Path,
+        warnings: &mut Vec<Warning>,
+    ) -> Option<SourceEncoding> {
+        let output = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &SourceEncoding::ffprobe_args(input_path),
+            )
+            .ok()
+            .filter(|output| output.status.success());
+        source_encoding_from_probe(output, warnings)
+    }
+
+    /// The path [`reverse_video`](Self::reverse_video) writes to: the input's name with
+    /// the suffix text ("-rev" by default) added before the extension, or in front of the
+    /// name if prefixing is enabled, or rendered from the output template.
+    ///
+    /// Symlinks and `..` in an existing input are resolved first, so the output lands next
+    /// to the real file, unless an [`output_dir`](VideoReverserBuilder::output_dir) is set.
+    pub fn generate_output_filename<P: AsRef<Path>>(&self, input_path: P) -> PathBuf {
+        self.numbered_output_filename(input_path.as_ref(), 1)
+    }
+
+    /// Like [`generate_output_filename`](Self::generate_output_filename), for the input at
+    /// 1-based position `index` of a batch
+    fn numbered_output_filename(&self, input_path: &Path, index: usize) -> PathBuf {
+        let input_path = &resolve_input(input_path);
+        let output_path = if let Some(template) = &self.output_template {
+            input_path.with_file_name(self.render_template(template, input_path, index))
+        } else {
+            let output_path = if self.prefix {
+                let mut new_name = OsString::from(&self.suffix);
+                new_name.push(input_path.file_name().unwrap_or_default());
+                input_path.with_file_name(new_name)
+            } else {
+                output_filename_with_suffix(input_path, &self.suffix)
+            };
+            match &self.output_format {
+                Some(extension) => output_path.with_extension(extension),
+                None => output_path,
+            }
+        };
+        match &self.output_dir {
+            Some(output_dir) => output_dir.join(output_path.file_name().unwrap_or_default()),
+            None => output_path,
+        }
+    }
+
+    /// Like [`numbered_output_filename`](Self::numbered_output_filename), for an input
+    /// found under `root` by a directory run: with an output directory the output keeps
+    /// the input's path relative to `root`
+    fn batch_output_filename(&self, root: &Path, input_path: &Path, index: usize) -> PathBuf {
+        let output_path = self.numbered_output_filename(input_path, index);
+        let Some(output_dir) = &self.output_dir else {
+            return output_path;
+        };
+        let subdir = input_path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .unwrap_or(Path::new(""));
+        output_dir
+            .join(subdir)
+            .join(output_path.file_name().unwrap_or_default())
+    }
+
+    /// Fills in the placeholders of `template` for `input_path`, giving a plain file name.
+    ///
+    /// Unknown placeholders are left as they are; [`validate_options`](Self::validate_options)
+    /// rejects them before anything runs.
+    fn render_template(&self, template: &str, input_path: &Path, index: usize) -> String {
+        let mut name = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            name.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let Some(len) = rest.find('}') else {
+                break;
+            };
+            let placeholder = &rest[1..len];
+            let lossy =
+                |part: Option<&OsStr>| part.unwrap_or_default().to_string_lossy().into_owned();
+            let value = match placeholder {
+                "stem" => lossy(input_path.file_stem()),
+                "ext" => match &self.output_format {
+                    Some(extension) => extension.clone(),
+                    None => lossy(input_path.extension()),
+                },
+                "parent" => lossy(input_path.parent().and_then(Path::file_name)),
+                "date" => utc_date(std::time::SystemTime::now()),
+                "index" => index.to_string(),
+                "duration" => self
+                    .input_duration(input_path)
+                    .map_or("unknown".to_string(), |d| d.as_secs().to_string()),
+                _ => rest[..=len].to_string(),
+            };
+            name.push_str(&value);
+            rest = &rest[len + 1..];
+        }
+        name.push_str(rest);
+        sanitize_file_name(&name)
+    }
+
+    /// Whether the file looks like something we produced, i.e. its name carries the
+    /// suffix text in the configured position or fits the output template
+    fn is_reversed_output(&self, path: &Path) -> bool {
+        let templated = self.output_template.as_deref().is_some_and(|template| {
+            path.file_name()
+                .and_then(OsStr::to_str)
+                .is_some_and(|name| matches_template(template, name))
+        });
+        if templated {
+            return true;
+        }
+        if self.suffix.is_empty() {
+            return false;
+        }
+        // Compared as bytes so that names that aren't valid UTF-8 are recognised too
+        let suffix = self.suffix.as_bytes();
+        if self.prefix {
+            path.file_name()
+                .is_some_and(|name| name.as_encoded_bytes().starts_with(suffix))
+        } else {
+            path.file_stem()
+                .is_some_and(|stem| stem.as_encoded_bytes().ends_with(suffix))
+        }
+    }
+
+    /// Reverses the input video, writing the result next to it with a "-rev" suffix
+    /// (see [`VideoReverserBuilder::suffix`])
+    ///
+    /// Symlinks and `..` components in the input are resolved first, so the output lands
+    /// next to the real file and the returned path is absolute. Use
+    /// [`reverse_video_to`](Self::reverse_video_to) to pick the output path yourself.
+    pub fn reverse_video<P: AsRef<Path>>(&self, input_path: P) -> Result<PathBuf, VideoError> {
+        self.reverse_video_with_mode(input_path, self.mode)
+    }
+
+    /// Reverses the input video like [`reverse_video`](Self::reverse_video), then probes the
+    /// result so callers can report on it without running ffprobe themselves.
+    ///
+    /// If ffprobe is unavailable or can't read the output, the duration and resolution are
+    /// left empty. This and any other non-fatal problem is listed in
+    /// [`warnings`](ReverseOutcome::warnings). In dry-run mode nothing is written, so
+    /// only the path is filled in.
+    pub fn reverse_video_detailed<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+    ) -> Result<ReverseOutcome, VideoError> {
+        self.reverse_detailed(input_path.as_ref(), None)
+    }
+
+    /// Starts reversing the input on a background thread and returns an iterator over
+    /// its events, for callers that would rather consume progress than pass a callback.
+    ///
+    /// The first event is [`Started`](ReverseEvent::Started), followed by
+    /// [`Progress`](ReverseEvent::Progress) updates parsed from ffmpeg's `-progress`
+    /// output, and the last is [`Finished`](ReverseEvent::Finished) with the same outcome
+    /// as [`reverse_video_detailed`](Self::reverse_video_detailed), or
+    /// [`Failed`](ReverseEvent::Failed). `next` blocks until the following event arrives.
+    ///
+    /// The thread works on a clone of this reverser, so the iterator borrows nothing and
+    /// can be moved to another thread. Dropping it early does not stop the reversal,
+    /// which runs to completion in the background; use a
+    /// [`cancel_token`](VideoReverserBuilder::cancel_token) to stop it.
+    pub fn reverse_video_events<P: AsRef<Path>>(&self, input_path: P) -> ReverseEvents {
+        let reverser = self.clone();
+        let input_path = input_path.as_ref().to_path_buf();
+        let (sender, events) = mpsc::channel();
+        let worker = std::thread::spawn(move || {
+            // Send errors mean the iterator was dropped, which doesn't stop the work
+            let _ = sender.send(ReverseEvent::Started);
+            let mut progress = |update| {
+                let _ = sender.send(ReverseEvent::Progress(update));
+            };
+            let event = match reverser.reverse_detailed(&input_path, Some(&mut progress)) {
+                Ok(outcome) => ReverseEvent::Finished(outcome),
+                Err(err) => ReverseEvent::Failed(err),
+            };
+            let _ = sender.send(event);
+        });
+        ReverseEvents {
+            events,
+            worker: Some(worker),
+        }
+    }
+
+    fn reverse_detailed(
+        &self,
+        input_path: &Path,
+        progress: Option<&mut dyn FnMut(ProgressUpdate)>,
+    ) -> Result<ReverseOutcome, VideoError> {
+        let output_path = self.generate_output_filename(input_path);
+        // Any other policy skips or fails on an existing output rather than replacing it
+        let overwrote_existing = output_path.exists() && self.overwrite == OverwritePolicy::Always;
+        let mut warnings = Vec::new();
+        let (path, elapsed) = self.reverse_timed(
+            Job::new(input_path, &output_path, self.mode),
+            progress,
+            &mut warnings,
+        )?;
+        if self.dry_run {
+            return Ok(ReverseOutcome {
+                warnings,
+                ..ReverseOutcome::parse(path, 0, elapsed, "")
+            });
+        }
+        let size = std::fs::metadata(&path)?.len();
+        let probe = self
+            .runner
+            .run(
+                self.ffprobe_path.as_os_str(),
+                &ReverseOutcome::ffprobe_args(&path),
+            )
+            .ok()
+            .filter(|output| output.status.success());
+        let stdout = match probe {
+            Some(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+            None => {
+                warn(&mut warnings, Warning::OutputNotProbed);
+                String::new()
+            }
+        };
+        Ok(ReverseOutcome {
+            overwrote_existing,
+            warnings,
+            ..ReverseOutcome::parse(path, size, elapsed, &stdout)
+        })
+    }
+
+    /// Reverses the input video like [`reverse_video`](Self::reverse_video), reporting progress.
+    ///
+    /// `progress` receives the completion fraction (0.0 to 1.0) roughly once per second,
+    /// based on the input duration reported by ffprobe. If ffprobe cannot determine the
+    /// duration, only the final 1.0 is reported.
+    pub fn reverse_video_with_progress<P, F>(
+        &self,
+        input_path: P,
+        mut progress: F,
+    ) -> Result<PathBuf, VideoError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(f64),
+    {
+        let input_path = input_path.as_ref();
+        let output_path = self.generate_output_filename(input_path);
+        self.reverse_into(
+            Job::new(input_path, &output_path, self.mode),
+            Some(&mut fraction_reporter(&mut progress)),
+        )
+    }
+
+    /// Reverses the streams selected by `mode` instead of the configured mode,
+    /// writing the result next to the input
+    pub fn reverse_video_with_mode<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        mode: ReverseMode,
+    ) -> Result<PathBuf, VideoError> {
+        let input_path = input_path.as_ref();
+        let output_path = self.generate_output_filename(input_path);
+        self.reverse_into(Job::new(input_path, &output_path, mode), None)
+    }
+
+    /// Reverses the input video into an explicit output path.
+    ///
+    /// Missing parent directories of the output path are created.
+    pub fn reverse_video_to<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: Q,
+    ) -> Result<PathBuf, VideoError> {
+        let job = Job::new(input_path.as_ref(), output_path.as_ref(), self.mode);
+        self.reverse_into(job, None)
+    }
+
+    /// Reverses the input video into an explicit output path, reporting progress like
+    /// [`reverse_video_with_progress`](Self::reverse_video_with_progress)
+    pub fn reverse_video_to_with_progress<P, Q, F>(
+        &self,
+        input_path: P,
+        output_path: Q,
+        mut progress: F,
+    ) -> Result<PathBuf, VideoError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        F: FnMut(f64),
+    {
+        let job = Job::new(input_path.as_ref(), output_path.as_ref(), self.mode);
+        self.reverse_into(job, Some(&mut fraction_reporter(&mut progress)))
+    }
+
+    /// Reverses the input and returns the resulting file's contents.
+    ///
+    /// The result is written to a scratch file in the [`temp_dir`](VideoReverserBuilder::temp_dir)
+    /// or the system temp directory, since MP4
+    /// and MOV need a seekable output to place their index; it is read back and removed
+    /// before returning, including on failure. The whole output is held in memory, so
+    /// this suits clips rather than long recordings. In dry-run mode nothing is written
+    /// and the returned buffer is empty.
+    pub fn reverse_to_bytes<P: AsRef<Path>>(&self, input_path: P) -> Result<Vec<u8>, VideoError> {
+        let input_path = input_path.as_ref();
+        let scratch = TempOutput::new(
+            &self
+                .scratch_dir()
+                .join(input_path.file_name().unwrap_or_default()),
+        );
+        self.reverse_into(Job::new(input_path, scratch.path(), self.mode), None)?;
+        if self.dry_run {
+            return Ok(Vec::new());
+        }
+        Ok(std::fs::read(scratch.path())?)
+    }
+
+    /// Reverses a video read from `reader`, such as an upload held in memory, and returns
+    /// the resulting file's contents like [`reverse_to_bytes`](Self::reverse_to_bytes).
+    ///
+    /// ffmpeg needs a seekable input to find the index of an MP4 or MOV, so the reader is
+    /// first copied to a scratch file in the [`temp_dir`](VideoReverserBuilder::temp_dir)
+    /// or the system temp directory. `format_hint` is that file's extension (`"mp4"`,
+    /// `".mov"`), from which ffmpeg infers the container and the output keeps it; it must
+    /// be one of the allowed extensions. The scratch file is removed before returning,
+    /// including on failure.
+    pub fn reverse_reader<R: Read>(
+        &self,
+        mut reader: R,
+        format_hint: &str,
+    ) -> Result<Vec<u8>, VideoError> {
+        let extension = format_hint.trim_start_matches('.');
+        if extension.is_empty() || extension.contains(['/', '\\']) {
+            return Err(VideoError::InvalidInput(format!(
+                "format hint {format_hint:?} is not a file extension"
+            )));
+        }
+        self.check_temp_dir()?;
+        let input = TempOutput::new(&self.scratch_dir().join(format!("input.{extension}")));
+        let mut file = std::fs::File::create(input.path())?;
+        std::io::copy(&mut reader, &mut file)?;
+        file.flush()?;
+        drop(file);
+        self.reverse_to_bytes(input.path())
+    }
+
+    /// Writes only the input's reversed audio to `output_path`, e.g. a `.wav` or `.mp3`
+    /// for sound design; ffmpeg picks the container and codec from its extension.
+    ///
+    /// The [`audio_options`](VideoReverserBuilder::audio_options) filters and the
+    /// [`fade`](VideoReverserBuilder::fade) apply, speed changes and repeats don't. Fails
+    /// with [`VideoError::NoAudioStream`] if ffprobe finds no audio in the input.
+    pub fn reverse_audio_to<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: Q,
+    ) -> Result<PathBuf, VideoError> {
+        let job = Job {
+            effect: Effect::AudioExtract,
+            ..Job::new(
+                input_path.as_ref(),
+                output_path.as_ref(),
+                ReverseMode::AudioOnly,
+            )
+        };
+        self.reverse_into(job, None)
+    }
+
+    /// Reverses the input into an animated GIF next to it, e.g. `clip.mp4` to `clip-rev.gif`.
+    ///
+    /// The GIF runs at `fps` frames per second and is scaled to `width` pixels wide
+    /// (keeping the aspect ratio), or kept at the source width if `None`. Colors come from
+    /// a palette generated for the clip. Audio is dropped.
+    pub fn reverse_to_gif<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        fps: u32,
+        width: Option<u32>,
+    ) -> Result<PathBuf, VideoError> {
+        if fps == 0 {
+            return Err(VideoError::InvalidInput(
+                "GIF frame rate must be positive".to_string(),
+            ));
+        }
+        if width == Some(0) {
+            return Err(VideoError::InvalidInput(
+                "GIF width must be positive".to_string(),
+            ));
+        }
+        let input_path = input_path.as_ref();
+        let output_path = self
+            .generate_output_filename(input_path)
+            .with_extension("gif");
+        let job = Job {
+            effect: Effect::Gif(Gif { fps, width }),
+            ..Job::new(input_path, &output_path, ReverseMode::Both)
+        };
+        self.reverse_into(job, None)
+    }
+
+    /// Writes a comparison clip next to the input, e.g. `clip.mp4` to `clip-compare.mp4`,
+    /// with the original on the left and the reversed video on the right.
+    ///
+    /// Both halves run for the full length of the clip, so they stay in step frame for
+    /// frame. Each half is labelled with `drawtext`, which needs an ffmpeg built with
+    /// libfreetype. Audio is dropped.
+    pub fn reverse_side_by_side<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+    ) -> Result<PathBuf, VideoError> {
+        let input_path = input_path.as_ref();
+        let output_path = output_filename_with_suffix(&resolve_input(input_path), "-compare");
+        let job = Job {
+            effect: Effect::SideBySide,
+            ..Job::new(input_path, &output_path, ReverseMode::Both)
+        };
+        self.reverse_into(job, None)
+    }
+
+    /// Writes a boomerang next to the input, e.g. `clip.mp4` to `clip-boomerang.mp4`: the
+    /// clip plays forward and then backward, `loops` times over.
+    ///
+    /// The audio is reversed along with the video, or left out if the input has none or
+    /// the audio options drop it. Every play is held in memory while ffmpeg reverses it,
+    /// so long clips are allowed but logged with a warning.
+    pub fn boomerang<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        loops: u32,
+    ) -> Result<PathBuf, VideoError> {
+        if loops == 0 {
+            return Err(VideoError::InvalidInput(
+                "Boomerang needs at least one loop".to_string(),
+            ));
+        }
+        let input_path = input_path.as_ref();
+        if let Some(duration) = self
+            .probe_duration(input_path)
+            .filter(|d| *d > BOOMERANG_WARN_SECONDS)
+        {
+            log::warn!(
+                "{} is {duration}s long; boomerangs of clips over {BOOMERANG_WARN_SECONDS}s need a lot of memory",
+                input_path.display()
+            );
+        }
+        let output_path = output_filename_with_suffix(&resolve_input(input_path), "-boomerang");
+        let job = Job {
+            effect: Effect::Boomerang(Boomerang { loops }),
+            ..Job::new(input_path, &output_path, ReverseMode::Both)
+        };
+        self.reverse_into(job, None)
+    }
+
+    /// Saves the frame at `at` of an already reversed clip as a JPEG next to it, e.g.
+    /// `clip-rev.mp4` to `clip-rev.jpg`. `Duration::ZERO` gives the reversed clip's first
+    /// frame, which is the original's last.
+    ///
+    /// `at` must lie within the clip; this is checked when ffprobe reports its duration.
+    pub fn extract_thumbnail<P: AsRef<Path>>(
+        &self,
+        reversed_path: P,
+        at: Duration,
+    ) -> Result<PathBuf, VideoError> {
+        let reversed_path = reversed_path.as_ref();
+        if let Some(duration) = self
+            .input_duration(reversed_path)
+            .filter(|duration| at >= *duration)
+        {
+            return Err(VideoError::InvalidInput(format!(
+                "Thumbnail time {}s is past the end of the {}s clip",
+                at.as_secs_f64(),
+                duration.as_secs_f64()
+            )));
+        }
+        let output_path = resolve_input(reversed_path).with_extension("jpg");
+        let job = Job {
+            effect: Effect::Thumbnail(at),
+            ..Job::new(reversed_path, &output_path, ReverseMode::Both)
+        };
+        self.reverse_into(job, None)
+    }
+
+    /// Writes the reversed video as numbered still images in `dir`, which is created if
+    /// needed, and returns how many frames were written.
+    ///
+    /// `pattern` names the frames with a single `%0Nd` token for the frame number,
+    /// counted from 1: `frame_%04d` gives `frame_0001.png`, `frame_0002.png` and so on.
+    /// The format's extension is appended unless the pattern already ends with it. Audio
+    /// is dropped.
+    ///
+    /// The overwrite policy applies to the first frame. When it allows overwriting, the
+    /// frames of an earlier run under the same pattern are removed first, so none are left
+    /// behind by a longer clip. Frames are written straight into `dir`, so a failed run
+    /// can leave part of the sequence. In dry-run mode nothing is written and the count
+    /// is 0.
+    pub fn reverse_to_image_sequence<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        dir: Q,
+        pattern: &str,
+        format: ImageFormat,
+    ) -> Result<usize, VideoError> {
+        let started = Instant::now();
+        let (input_path, dir) = (input_path.as_ref(), dir.as_ref());
+        let has_extension = Path::new(pattern).extension().is_some_and(|ext| {
+            format
+                .extensions()
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        });
+        let pattern = if has_extension {
+            pattern.to_string()
+        } else {
+            format!("{pattern}.{}", format.extensions()[0])
+        };
+        let result = self.run_image_sequence(input_path, dir, &pattern, format);
+        let output_path = dir.join(&pattern);
+        self.record_metrics(
+            input_path,
+            &output_path,
+            started,
+            result.as_ref().map(|_| ()),
+        );
+        result
+    }
+
+    /// The body of [`reverse_to_image_sequence`](Self::reverse_to_image_sequence), for a
+    /// `pattern` that ends with the format's extension
+    fn run_image_sequence(
+        &self,
+        input_path: &Path,
+        dir: &Path,
+        pattern: &str,
+        format: ImageFormat,
+    ) -> Result<usize, VideoError> {
+        let Some((prefix, width, suffix)) =
+            split_sequence_pattern(pattern).filter(|_| !pattern.contains(['/', '\\']))
+        else {
+            return Err(VideoError::InvalidInput(format!(
+                "Image sequence pattern {pattern:?} must be a file name with one %0Nd token, e.g. frame_%04d"
+            )));
+        };
+        let frame = |number: usize| dir.join(format!("{prefix}{number:0width$}{suffix}"));
+        let count_frames = || (1..).map(frame).take_while(|path| path.exists()).count();
+
+        let output_path = dir.join(pattern);
+        self.validate_paths(input_path, &output_path)?;
+        let warnings = &mut Vec::new();
+        self.validate_options(ReverseMode::Both, warnings)?;
+        if self.skip_existing_output(&frame(1))? {
+            log::info!(
+                "Skipping {}: {} already exists",
+                input_path.display(),
+                frame(1).display()
+            );
+            return Ok(count_frames());
+        }
+        if self.dry_run {
+            return Ok(0);
+        }
+
+        self.check_ffmpeg(warnings)?;
+        self.validate_input(input_path, ReverseMode::Both, warnings)?;
+        let job = Job {
+            effect: Effect::ImageSequence(format),
+            format: Some("image2"),
+            ..Job::new(input_path, &output_path, ReverseMode::Both)
+        };
+        let job = Job {
+            fade: self.fade_for(&job, || self.probe_duration(input_path))?,
+            ..job
+        };
+        std::fs::create_dir_all(dir).map_err(|err| not_writable(dir, err))?;
+        for stale in (1..).map(frame).take_while(|path| path.exists()) {
+            std::fs::remove_file(stale)?;
+        }
+
+        self.execute(job, None, warnings)?;
+        Ok(count_frames())
+    }
+
+    /// Joins `inputs` in order into one recording and reverses it as a whole into
+    /// `output_path`, so the last frame of the last input comes first.
+    ///
+    /// The inputs are joined with ffmpeg's concat demuxer, which needs them to share the
+    /// video codec, dimensions and audio codec; ffprobe checks this up front and a
+    /// mismatch fails with [`VideoError::InvalidInput`] naming the odd file. Without
+    /// ffprobe the check is skipped with a warning. Like any reversal, the joined clip is
+    /// held in memory while ffmpeg reverses it.
+    pub fn reverse_concat<P: AsRef<Path>>(
+        &self,
+        inputs: &[PathBuf],
+        output_path: P,
+    ) -> Result<PathBuf, VideoError> {
+        let started = Instant::now();
+        let output_path = output_path.as_ref();
+        let Some(first) = inputs.first() else {
+            return Err(VideoError::InvalidInput(
+                "Concatenating needs at least one input".to_string(),
+            ));
+        };
+        let result = self.run_concat(inputs, output_path);
+        self.record_metrics(first, output_path, started, result.as_ref().map(|_| ()));
+        result
+    }
+
+    /// The body of [`reverse_concat`](Self::reverse_concat), for at least one input
+    fn run_concat(&self, inputs: &[PathBuf], output_path: &Path) -> Result<PathBuf, VideoError> {
+        let first = &inputs[0];
+        for input in inputs {
+            self.validate_paths(input, output_path)?;
+        }
+        let warnings = &mut Vec::new();
+        self.validate_options(self.mode, warnings)?;
+        if self.skip_existing_output(output_path)? {
+            log::info!(
+                "Skipping {}: {} already exists",
+                first.display(),
+                output_path.display()
+            );
+            return Ok(output_path.to_path_buf());
+        }
+        if self.dry_run {
+            return Ok(output_path.to_path_buf());
+        }
+
+        log::info!(
+            "Reversing {} inputs from {} -> {}",
+            inputs.len(),
+            first.display(),
+            output_path.display()
+        );
+        self.check_ffmpeg(warnings)?;
+        let infos = self.probe_concat_inputs(inputs, warnings)?;
+        let has_audio = infos
+            .first()
+            .is_none_or(|(_, info)| info.audio_codec.is_some());
+        if !has_audio {
+            if self.mode == ReverseMode::AudioOnly {
+                return Err(VideoError::NoAudioStream(first.clone()));
+            }
+            warn(warnings, Warning::NoAudioStream);
+        }
+        let total_duration = || {
+            infos
+                .iter()
+                .map(|(_, info)| info.duration.map(|d| d.as_secs_f64()))
+                .sum::<Option<f64>>()
+                .filter(|_| infos.len() == inputs.len())
+        };
+
+        prepare_output_dir(output_path)?;
+        self.check_temp_dir()?;
+        let list = TempOutput::new(&self.scratch_dir().join("concat.txt"));
+        let inputs = inputs
+            .iter()
+            .map(|input| input.canonicalize())
+            .collect::<Result<Vec<_>, _>>()?;
+        std::fs::write(list.path(), concat_list(&inputs)?)?;
+
+        let job = Job {
+            has_audio,
+            concat: true,
+            ..Job::new(list.path(), output_path, self.mode)
+        };
+        let job = Job {
+            fade: self.fade_for(&job, total_duration)?,
+            ..job
+        };
+        self.execute(job, None, warnings)?;
+        Ok(output_path.to_path_buf())
+    }
+
+    /// Probes each of `inputs` for [`reverse_concat`](Self::reverse_concat), failing on
+    /// the first whose streams don't match the first input's. Empty if ffprobe is missing.
+    fn probe_concat_inputs<'a>(
+        &self,
+        inputs: &'a [PathBuf],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<(&'a Path, VideoInfo)>, VideoError> {
+        let mut infos: Vec<(&Path, VideoInfo)> = Vec::new();
+        for input in inputs {
+            let info = match self.probe_video_info(input) {
+                Ok(info) => info,
+                Err(VideoError::FFprobeNotFound) => {
+                    warn(warnings, Warning::FFprobeUnavailable);
+                    return Ok(Vec::new());
+                }
+                Err(err) => return Err(err),
+            };
+            if info.video_codec.is_none() {
+                return Err(VideoError::NotAVideo(format!(
+                    "{} contains no video stream",
+                    input.display()
+                )));
+            }
+            if let Some((first, expected)) = infos.first() {
+                let mismatch = if info.video_codec != expected.video_codec {
+                    Some("video codec")
+                } else if (info.width, info.height) != (expected.width, expected.height) {
+                    Some("dimensions")
+                } else if info.audio_codec != expected.audio_codec {
+                    Some("audio codec")
+                } else {
+                    None
+                };
+                if let Some(mismatch) = mismatch {
+                    return Err(VideoError::InvalidInput(format!(
+                        "Can't concatenate {}: its {mismatch} differs from {}",
+                        input.display(),
+                        first.display()
+                    )));
+                }
+            }
+            infos.push((input, info));
+        }
+        Ok(infos)
+    }
+
+    /// Reverses only the `start..end` window of the input.
+    ///
+    /// The output is written next to the input with the range in its name,
+    /// e.g. `clip-rev-30s-60s.mp4`.
+    pub fn reverse_segment<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        start: Duration,
+        end: Duration,
+    ) -> Result<PathBuf, VideoError> {
+        let input_path = input_path.as_ref();
+        let suffix = format!("-rev-{}s-{}s", start.as_secs_f64(), end.as_secs_f64());
+        let output_path = output_filename_with_suffix(&resolve_input(input_path), &suffix);
+        self.reverse_segment_to(input_path, output_path, start, end)
+    }
+
+    /// Reverses only the `start..end` window of the input into an explicit output path.
+    ///
+    /// `start` must come before `end`, and `end` must not exceed the clip duration
+    /// reported by ffprobe. If ffprobe can't report a duration, only the ordering is checked.
+    /// See [`VideoReverserBuilder::accurate_seek`] for how the start of the window is found.
+    pub fn reverse_segment_to<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: Q,
+        start: Duration,
+        end: Duration,
+    ) -> Result<PathBuf, VideoError> {
+        let started = Instant::now();
+        let (input_path, output_path) = (input_path.as_ref(), output_path.as_ref());
+        let result = self.run_segment(input_path, output_path, start, end);
+        self.record_metrics(
+            input_path,
+            output_path,
+            started,
+            result.as_ref().map(|_| ()),
+        );
+        result
+    }
+
+    /// The body of [`reverse_segment_to`](Self::reverse_segment_to), which doesn't report
+    /// to the metrics sink, so a segment can also be one piece of a larger reversal
+    fn run_segment(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        start: Duration,
+        end: Duration,
+    ) -> Result<PathBuf, VideoError> {
+        if start >= end {
+            return Err(VideoError::InvalidInput(
+                "Segment start must be before its end".to_string(),
+            ));
+        }
+        self.validate_paths(input_path, output_path)?;
+
+        if let Some(duration) = self.probe_duration(input_path) {
+            if end.as_secs_f64() > duration {
+                return Err(VideoError::InvalidInput(format!(
+                    "Segment end {}s is past the end of the clip ({duration}s)",
+                    end.as_secs_f64()
+                )));
+            }
+        }
+
+        let job = Job {
+            segment: Some(Segment { start, end }),
+            ..Job::new(input_path, output_path, self.mode)
+        };
+        self.run_job(job, None, &mut Vec::new())
+            .map(|(path, _)| path)
+    }
+
+    /// Finds the scene cuts of the input: the times of frames that differ from the previous
+    /// one by more than `threshold`, from 0.0 to 1.0, according to ffmpeg's scene score.
+    /// Around 0.3 catches most hard cuts.
+    pub fn detect_scenes<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        threshold: f64,
+    ) -> Result<Vec<Duration>, VideoError> {
+        if !(threshold > 0.0 && threshold < 1.0) {
+            return Err(VideoError::InvalidInput(
+                "Scene threshold must be between 0 and 1".to_string(),
+            ));
+        }
+        let input_path = input_path.as_ref();
+        self.validate_paths(input_path, &self.generate_output_filename(input_path))?;
+        self.check_ffmpeg(&mut Vec::new())?;
+        let mut args: Vec<OsString> = vec!["-hide_banner".into(), "-i".into(), input_path.into()];
+        args.extend([
+            "-an".into(),
+            "-vf".into(),
+            format!("select='gt(scene,{threshold})',showinfo").into(),
+            "-f".into(),
+            "null".into(),
+            "-".into(),
+        ]);
+        let command = self.ffmpeg_command(args);
+        log::debug!("Running {command}");
+        let output = self.run_ffmpeg(&command)?;
+        check_ffmpeg_output(&output, &command)?;
+        Ok(parse_scene_cuts(&String::from_utf8_lossy(&output.stderr)))
+    }
+
+    /// Reverses each scene of the input in place while keeping the scenes in their
+    /// forward order, writing e.g. `clip-scene-rev.mp4` next to the input. Returns the
+    /// output path and the cuts found by [`detect_scenes`](Self::detect_scenes).
+    ///
+    /// Every scene is reversed like [`reverse_segment`](Self::reverse_segment) into a temp
+    /// file beside the output, and the pieces are then joined without re-encoding. This
+    /// needs the input duration from ffprobe. In dry-run mode nothing is detected and the
+    /// list of cuts is empty.
+    pub fn reverse_per_scene<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        threshold: f64,
+    ) -> Result<(PathBuf, Vec<Duration>), VideoError> {
+        let started = Instant::now();
+        let input_path = input_path.as_ref();
+        let output_path = output_filename_with_suffix(&resolve_input(input_path), "-scene-rev");
+        let result = self.run_per_scene(input_path, output_path.clone(), threshold);
+        self.record_metrics(
+            input_path,
+            &output_path,
+            started,
+            result.as_ref().map(|_| ()),
+        );
+        result
+    }
+
+    /// The body of [`reverse_per_scene`](Self::reverse_per_scene)
+    fn run_per_scene(
+        &self,
+        input_path: &Path,
+        output_path: PathBuf,
+        threshold: f64,
+    ) -> Result<(PathBuf, Vec<Duration>), VideoError> {
+        self.validate_paths(input_path, &output_path)?;
+        self.validate_options(self.mode, &mut Vec::new())?;
+        if self.dry_run || self.skip_existing_output(&output_path)? {
+            return Ok((output_path, Vec::new()));
+        }
+
+        let cuts = self.detect_scenes(input_path, threshold)?;
+        let duration = self.input_duration(input_path).ok_or_else(|| {
+            VideoError::InvalidInput(format!(
+                "Can't split {} into scenes: ffprobe doesn't report its duration",
+                input_path.display()
+            ))
+        })?;
+        let mut bounds = vec![Duration::ZERO];
+        bounds.extend(
+            cuts.iter()
+                .copied()
+                .filter(|cut| !cut.is_zero() && *cut < duration),
+        );
+        bounds.push(duration);
+
+        prepare_output_dir(&output_path)?;
+        self.check_temp_dir()?;
+        let temp_dir = self.temp_dir.as_deref();
+        let pieces: Vec<TempOutput> = bounds
+            .windows(2)
+            .map(|_| TempOutput::new_in(temp_dir, &output_path))
+            .collect();
+        let list = concat_list(pieces.iter().map(TempOutput::path))?;
+        for (piece, window) in pieces.iter().zip(bounds.windows(2)) {
+            self.run_segment(input_path, piece.path(), window[0], window[1])?;
+        }
+        let list_file = TempOutput::new_in(temp_dir, &output_path.with_extension("txt"));
+        std::fs::write(list_file.path(), list)?;
+
+        let temp = TempOutput::new_in(temp_dir, &output_path);
+        let mut args: Vec<OsString> =
+            vec!["-f".into(), "concat".into(), "-safe".into(), "0".into()];
+        args.extend(["-i".into(), list_file.path().into()]);
+        args.extend(["-c".into(), "copy".into(), "-y".into(), temp.path().into()]);
+        let command = self.ffmpeg_command(args);
+        log::debug!("Running {command}");
+        let result = self.run_ffmpeg(&command)?;
+        check_ffmpeg_output(&result, &command)?;
+        temp.persist(&output_path)?;
+        Ok((output_path, cuts))
+    }
+
+    /// Reverses a video read from this process's stdin, writing it to stdout as `format`.
+    ///
+    /// `format` is an ffmpeg muxer name and must be one of [`STREAMABLE_FORMATS`].
+    /// The reverse filters need the whole stream before they can emit the first frame,
+    /// so ffmpeg buffers the entire decoded input in memory; this is only practical
+    /// for short clips. Input validation with ffprobe and source-encoding matching are
+    /// skipped, since probing would consume the input.
+    pub fn reverse_stream(&self, format: &str) -> Result<(), VideoError> {
+        let started = Instant::now();
+        let result = self.run_stream(format);
+        self.record_metrics(
+            Path::new("pipe:0"),
+            Path::new("pipe:1"),
+            started,
+            result.as_ref().copied(),
+        );
+        result
+    }
+
+    /// The body of [`reverse_stream`](Self::reverse_stream)
+    fn run_stream(&self, format: &str) -> Result<(), VideoError> {
+        if !STREAMABLE_FORMATS.contains(&format) {
+            return Err(VideoError::InvalidInput(format!(
+                "Output format '{}' can't be written to a pipe; use one of: {}",
+                format,
+                STREAMABLE_FORMATS.join(", ")
+            )));
+        }
+        let warnings = &mut Vec::new();
+        self.validate_options(self.mode, warnings)?;
+        if self.dry_run {
+            return Ok(());
+        }
+        self.check_ffmpeg(warnings)?;
+
+        let job = Job {
+            format: Some(format),
+            ..Job::new(Path::new("pipe:0"), Path::new("pipe:1"), self.mode)
+        };
+        // A pipe can't be probed without consuming it
+        let job = Job {
+            fade: self.fade_for(&job, || None)?,
+            ..job
+        };
+        let command = self.ffmpeg_command(self.ffmpeg_args(&job, false, None, warnings));
+        log::debug!("Running {command}");
+        let result = self.runner.run_piped(&command.program, &command.args)?;
+        check_ffmpeg_output(&result, &command)
+    }
+
+    /// Reverses every video in `dir` whose extension is allowed, collecting a result per file.
+    ///
+    /// Files whose name already carries the "-rev" suffix, or fits the
+    /// [`output_template`](VideoReverserBuilder::output_template), are skipped so earlier
+    /// outputs aren't reversed again. One failure doesn't stop the rest of the batch, but a
+    /// [cancellation](VideoReverserBuilder::cancel_token) does; a directory that can't be
+    /// read shows up as a failed entry for that directory.
+    pub fn reverse_directory<P: AsRef<Path>>(
+        &self,
+        dir: P,
+    ) -> Vec<(PathBuf, Result<PathBuf, VideoError>)> {
+        let dir = dir.as_ref();
+        let mut results = Vec::new();
+        let mut inputs = Vec::new();
+        self.collect_inputs(dir, &mut inputs, &mut results);
+        inputs.sort();
+        for (index, input) in inputs.into_iter().enumerate() {
+            if self.is_cancelled() {
+                results.push((input, Err(VideoError::Cancelled)));
+                break;
+            }
+            let output = self.batch_output_filename(dir, &input, index + 1);
+            let result = self.reverse_video_to(&input, output);
+            let cancelled = matches!(result, Err(VideoError::Cancelled));
+            results.push((input, result));
+            if cancelled {
+                break;
+            }
+        }
+        results
+    }
+
+    /// Like [`reverse_directory`](Self::reverse_directory), but skips inputs that haven't
+    /// changed since an earlier run, for batches that are re-run over the same folder.
+    ///
+    /// An input is [`Skipped`](BatchStatus::Skipped) when its output exists and its size
+    /// and modification time match the signature in `cache`, or when its output exists
+    /// under [`OverwritePolicy::Skip`]. Every successful reversal
+    /// records the input's signature and every failure forgets it; a dry run leaves the
+    /// cache alone. Persist the cache with [`InputCache::save`] between runs.
+    pub fn reverse_directory_incremental<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        cache: &mut InputCache,
+    ) -> Vec<(PathBuf, Result<BatchStatus, VideoError>)> {
+        let dir = dir.as_ref();
+        let mut errors = Vec::new();
+        let mut inputs = Vec::new();
+        self.collect_inputs(dir, &mut inputs, &mut errors);
+        inputs.sort();
+        let mut results: Vec<_> = errors
+            .into_iter()
+            .map(|(path, result)| (path, result.map(BatchStatus::Reversed)))
+            .collect();
+        for (index, input) in inputs.into_iter().enumerate() {
+            if self.is_cancelled() {
+                results.push((input, Err(VideoError::Cancelled)));
+                break;
+            }
+            let output = self.batch_output_filename(dir, &input, index + 1);
+            // Read before reversing, so a change made meanwhile is caught next run
+            let signature = InputSignature::of(&input).ok();
+            if signature.is_some() && cache.get(&input) == signature.as_ref() && output.exists() {
+                log::info!("Skipping unchanged {}", input.display());
+                results.push((input, Ok(BatchStatus::Skipped(output))));
+                continue;
+            }
+            if self.overwrite == OverwritePolicy::Skip && output.exists() {
+                log::info!(
+                    "Skipping {}: {} already exists",
+                    input.display(),
+                    output.display()
+                );
+                results.push((input, Ok(BatchStatus::Skipped(output))));
+                continue;
+            }
+            let result = self.reverse_video_to(&input, output);
+            match (&result, signature) {
+                _ if self.dry_run => {}
+                (Ok(_), Some(signature)) => cache.insert(input.clone(), signature),
+                _ => {
+                    cache.remove(&input);
+                }
+            }
+            let cancelled = matches!(result, Err(VideoError::Cancelled));
+            results.push((input, result.map(BatchStatus::Reversed)));
+            if cancelled {
+                break;
+            }
+        }
+        results
+    }
+
+    /// Reverses each of `inputs` with the same settings, collecting a result per file in
+    /// the order given. One failure doesn't stop the rest.
+    pub fn reverse_multiple(
+        &self,
+        inputs: &[PathBuf],
+    ) -> Vec<(PathBuf, Result<PathBuf, VideoError>)> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let output = self.numbered_output_filename(input, index + 1);
+                (input.clone(), self.reverse_video_to(input, output))
+            })
+            .collect()
+    }
+
+    /// Like [`reverse_multiple`](Self::reverse_multiple), but writes each input to the
+    /// path `output_for` returns for it.
+    pub fn reverse_multiple_with<F: Fn(&Path) -> PathBuf>(
+        &self,
+        inputs: &[PathBuf],
+        output_for: F,
+    ) -> Vec<(PathBuf, Result<PathBuf, VideoError>)> {
+        inputs
+            .iter()
+            .map(|input| {
+                let result = self.reverse_video_to(input, output_for(input));
+                (input.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Like [`reverse_directory`](Self::reverse_directory), but runs up to
+    /// `max_concurrent` ffmpeg processes at once.
+    ///
+    /// ffmpeg is already multi-threaded, so [`default_concurrency`] (half the available
+    /// cores) is a sensible limit; passing 0 selects it. Unless
+    /// [`threads`](VideoReverserBuilder::threads) is set, each run is limited to its share
+    /// of the available cores. Results are sorted by input path. A panic while processing
+    /// one file is reported as [`VideoError::WorkerPanic`] for that file and doesn't affect
+    /// the others.
+    pub fn reverse_directory_parallel<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        max_concurrent: usize,
+    ) -> Vec<(PathBuf, Result<PathBuf, VideoError>)> {
+        let dir = dir.as_ref();
+        let mut results = Vec::new();
+        let mut inputs = Vec::new();
+        self.collect_inputs(dir, &mut inputs, &mut results);
+        // Sorted like the sequential run, so `{index}` in an output template matches
+        inputs.sort();
+
+        let max_concurrent = match max_concurrent {
+            0 => default_concurrency(),
+            n => n,
+        };
+        let workers = max_concurrent.min(inputs.len());
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let shared = VideoReverser {
+            threads: self
+                .threads
+                .or((workers > 1).then(|| (cores / workers).max(1) as u32)),
+            ..self.clone()
+        };
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                let (sender, next, inputs, shared) = (sender.clone(), &next, &inputs, &shared);
+                scope.spawn(move || {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(input) = inputs.get(index) else {
+                            break;
+                        };
+                        if shared.is_cancelled() {
+                            let _ = sender.send((input.clone(), Err(VideoError::Cancelled)));
+                            break;
+                        }
+                        let reverse = || {
+                            shared.reverse_video_to(
+                                input,
+                                shared.batch_output_filename(dir, input, index + 1),
+                            )
+                        };
+                        let result = panic::catch_unwind(AssertUnwindSafe(reverse)).unwrap_or_else(
+                            |payload| Err(VideoError::WorkerPanic(panic_message(payload.as_ref()))),
+                        );
+                        // The receiver outlives the scope, so sending can't fail
+                        let _ = sender.send((input.clone(), result));
+                    }
+                });
+            }
+        });
+        drop(sender);
+
+        results.extend(receiver);
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        results
+    }
+
+    /// Gathers the reversible files under `dir`, recording unreadable directories in `errors`
+    fn collect_inputs(
+        &self,
+        dir: &Path,
+        inputs: &mut Vec<PathBuf>,
+        errors: &mut Vec<(PathBuf, Result<PathBuf, VideoError>)>,
+    ) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push((dir.to_path_buf(), Err(e.into())));
+                return;
+            }
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    errors.push((dir.to_path_buf(), Err(e.into())));
+                    continue;
+                }
+            };
+            if path.is_dir() {
+                // Earlier outputs written into the tree aren't inputs
+                let is_output_dir = self
+                    .output_dir
+                    .as_deref()
+                    .is_some_and(|output_dir| is_same_file(&path, output_dir));
+                if self.recursive && !is_output_dir {
+                    self.collect_inputs(&path, inputs, errors);
+                }
+            } else if self.is_allowed_extension(&path) && !self.is_reversed_output(&path) {
+                inputs.push(path);
+            }
+        }
+    }
+
+    /// Builds the ffmpeg command that [`reverse_video`](Self::reverse_video) would run,
+    /// without executing anything.
+    ///
+    /// The planned command writes straight to the output path; the reverse methods
+    /// themselves write to a temporary sibling and rename it into place on success.
+    pub fn plan_command<P: AsRef<Path>>(&self, input_path: P) -> Result<FfmpegCommand, VideoError> {
+        let input_path = input_path.as_ref();
+        let output_path = self.generate_output_filename(input_path);
+        self.validate_paths(input_path, &output_path)?;
+        let warnings = &mut Vec::new();
+        self.validate_options(self.mode, warnings)?;
+        let job = Job::new(input_path, &output_path, self.mode);
+        let job = Job {
+            fade: self.fade_for(&job, || self.probe_duration(input_path))?,
+            ..job
+        };
+        Ok(self.command_for(&job, false, warnings))
+    }
+
+    /// The ffmpeg arguments that reverse `input_path` into `output_path` with this
+    /// configuration, for running ffmpeg yourself.
+    ///
+    /// This is pure: nothing is validated, probed or run. Unlike
+    /// [`plan_command`](Self::plan_command) the source encoding isn't matched, no
+    /// [`fade`](VideoReverserBuilder::fade) is applied, and the input is assumed to have
+    /// an audio stream.
+    pub fn build_ffmpeg_args<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: Q,
+    ) -> Vec<OsString> {
+        let job = Job::new(input_path.as_ref(), output_path.as_ref(), self.mode);
+        self.ffmpeg_args(&job, false, None, &mut Vec::new())
+    }
+
+    fn command_for(&self, job: &Job, progress: bool, warnings: &mut Vec<Warning>) -> FfmpegCommand {
+        let encoding = if self.match_source_encoding {
+            self.probe_source_encoding(job.input, warnings)
+        } else {
+            None
+        };
+        self.ffmpeg_command(self.ffmpeg_args(job, progress, encoding.as_ref(), warnings))
+    }
+
+    /// The configured ffmpeg run with `args`
+    fn ffmpeg_command(&self, args: Vec<OsString>) -> FfmpegCommand {
+        FfmpegCommand {
+            program: self.ffmpeg_path.clone().into_os_string(),
+            args,
+        }
+    }
+
+    /// Builds the ffmpeg arguments that carry out `job`
+    fn ffmpeg_args(
+        &self,
+        job: &Job,
+        progress: bool,
+        encoding: Option<&SourceEncoding>,
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<OsString> {
+        // The configured encoder replaces the source's video codec, so only encode when
+        // the video is actually re-encoded
+        let defaults = ContainerDefaults::for_output(job.format, job.output);
+        let encoder = self
+            .encoder
+            .as_deref()
+            .or(defaults.video_encoder)
+            .filter(|_| self.job_encodes_video(job));
+        // What ffmpeg will encode with, which decides the encoder options that apply
+        let actual_encoder = encoder.or(defaults.implied_video_encoder);
+        let mut args: Vec<OsString> = Vec::new();
+        if progress {
+            args.extend(["-progress", "pipe:1", "-nostats"].map(OsString::from));
+        }
+        if self.quiet {
+            args.extend(["-hide_banner", "-loglevel", "error"].map(OsString::from));
+        } else if self.verbose_ffmpeg {
+            args.extend(["-loglevel", "verbose"].map(OsString::from));
+        }
+        if let Some(hwaccel) = encoder.and_then(hwaccel_for) {
+            args.extend(["-hwaccel", hwaccel].map(OsString::from));
+        }
+        let segment = job.segment.map(|segment| {
+            [
+                "-ss".into(),
+                segment.start.as_secs_f64().to_string().into(),
+                "-to".into(),
+                segment.end.as_secs_f64().to_string().into(),
+            ]
+        });
+        // As input options the range seeks on keyframes; as output options ffmpeg
+        // decodes from the start and drops frames until the exact timestamp
+        if let (Some(range), false) = (&segment, self.accurate_seek) {
+            args.extend(range.iter().cloned());
+        }
+        if let Effect::Thumbnail(at) = job.effect {
+            args.extend(["-ss".into(), at.as_secs_f64().to_string().into()]);
+        }
+        // The list holds absolute paths, which the demuxer only accepts when not "safe"
+        if job.concat {
+            args.extend(["-f", "concat", "-safe", "0"].map(OsString::from));
+        }
+        let rotation = match job.effect {
+            Effect::Reverse if self.auto_rotate && self.encodes_video(job.mode) => {
+                rotation_filters(job.rotation)
+            }
+            _ => &[],
+        };
+        // The rotation is applied below, so ffmpeg mustn't apply it as well
+        if !rotation.is_empty() {
+            args.push("-noautorotate".into());
+        }
+        args.extend(["-i".into(), job.input.into()]);
+        if let (Some(range), true) = (segment, self.accurate_seek) {
+            args.extend(range);
+        }
+        match job.effect {
+            Effect::Reverse => {
+                // Any -map turns off ffmpeg's own stream choice, so both are mapped
+                if self.video_stream.is_some() || self.audio_stream.is_some() {
+                    let video = self
+                        .video_stream
+                        .map_or("0:v:0".to_string(), |index| format!("0:{index}"));
+                    args.extend(["-map".into(), video.into()]);
+                    if job.has_audio {
+                        let audio = self
+                            .audio_stream
+                            .map_or("0:a:0?".to_string(), |index| format!("0:{index}"));
+                        args.extend(["-map".into(), audio.into()]);
+                    }
+                    if !self.drop_non_av_streams {
+                        args.extend(["-map", "0:s:0?"].map(OsString::from));
+                    }
+                }
+                let mut video_filters: Vec<String> =
+                    rotation.iter().map(|filter| filter.to_string()).collect();
+                if job.mode.reverses_video() {
+                    if let Some((width, height)) = job.crop {
+                        video_filters.push(format!("crop={width}:{height}"));
+                    }
+                    if let Some(fps) = self.normalize_fps {
+                        video_filters.push(format!("fps={fps}"));
+                    }
+                    video_filters.push("reverse".to_string());
+                    video_filters.extend(self.video_filters.iter().cloned());
+                }
+                if let Some(speed) = self.speed {
+                    video_filters.push(format!("setpts=PTS/{speed}"));
+                }
+                if self.repeat > 1 {
+                    video_filters.push(repeat_filter(self.repeat, false));
+                }
+                if let Some(fade) = job.fade.filter(|_| job.mode.reverses_video()) {
+                    video_filters.push(fade.filters("fade"));
+                }
+                if video_filters.is_empty() {
+                    args.extend(["-c:v".into(), "copy".into()]);
+                } else {
+                    args.extend(["-vf".into(), video_filters.join(",").into()]);
+                }
+                // Audio arguments fail on inputs without an audio stream
+                if job.has_audio {
+                    args.extend(self.audio.ffmpeg_args(
+                        job.mode,
+                        self.speed,
+                        self.repeat,
+                        job.fade,
+                    ));
+                    if let Some(audio_encoder) = defaults
+                        .audio_encoder
+                        .filter(|_| self.audio.needs_encode(job.mode, self.speed, self.repeat))
+                    {
+                        args.extend(["-c:a".into(), audio_encoder.into()]);
+                    }
+                }
+                if let Some(encoder) = encoder {
+                    args.extend(["-c:v".into(), encoder.into()]);
+                }
+                if self.encodes_video(job.mode) {
+                    args.extend(self.quality_args(actual_encoder, warnings));
+                    args.extend(self.bitrate_args());
+                    if let Some(pass) = job.pass {
+                        args.extend([
+                            "-pass".into(),
+                            pass.number.to_string().into(),
+                            "-passlogfile".into(),
+                            pass.log.into(),
+                        ]);
+                    }
+                }
+                if !rotation.is_empty() {
+                    args.extend(["-metadata:s:v:0", "rotate=0"].map(OsString::from));
+                }
+                if let Some(encoding) = encoding {
+                    // Configured settings replace what would be copied from the source
+                    let mut encoding = encoding.clone();
+                    if encoder.is_some() {
+                        encoding.video_codec = None;
+                    }
+                    if self.target_bitrate.is_some() {
+                        encoding.video_bitrate = None;
+                    }
+                    args.extend(encoding.ffmpeg_args(
+                        self.encodes_video(job.mode),
+                        self.audio.needs_encode(job.mode, self.speed, self.repeat),
+                    ));
+                }
+            }
+            Effect::Gif(gif) => {
+                args.extend(["-vf".into(), gif.filter(job.fade).into(), "-an".into()]);
+            }
+            Effect::SideBySide => {
+                args.extend(["-filter_complex", SIDE_BY_SIDE_FILTER, "-an"].map(OsString::from));
+                args.extend(self.video_encode_args(encoder, actual_encoder, warnings));
+            }
+            Effect::Boomerang(boomerang) => {
+                let audio = job.has_audio && !self.audio.drop;
+                args.extend([
+                    "-filter_complex".into(),
+                    boomerang.filter(audio, job.fade).into(),
+                    "-map".into(),
+                    "[v]".into(),
+                ]);
+                if audio {
+                    args.extend(["-map", "[a]"].map(OsString::from));
+                    if let Some(audio_encoder) = defaults.audio_encoder {
+                        args.extend(["-c:a".into(), audio_encoder.into()]);
+                    }
+                } else {
+                    args.push("-an".into());
+                }
+                args.extend(self.video_encode_args(encoder, actual_encoder, warnings));
+            }
+            Effect::Thumbnail(_) => {
+                args.extend(["-frames:v", "1", "-an"].map(OsString::from));
+            }
+            Effect::ImageSequence(format) => {
+                let fade = job.fade.map(|fade| format!(",{}", fade.filters("fade")));
+                let filters = format!("reverse{}", fade.unwrap_or_default());
+                args.extend(["-vf".into(), filters.into(), "-an".into()]);
+                // The default JPEG quality is too low for compositing
+                if format == ImageFormat::Jpeg {
+                    args.extend(["-q:v", "2"].map(OsString::from));
+                }
+            }
+            Effect::AudioExtract => {
+                if let Some(index) = self.audio_stream {
+                    args.extend(["-map".into(), format!("0:{index}").into()]);
+                }
+                args.push("-vn".into());
+                args.extend(
+                    self.audio
+                        .ffmpeg_args(ReverseMode::AudioOnly, None, 1, job.fade),
+                );
+            }
+        }
+        if let Some(format) = self
+            .pixel_format
+            .as_deref()
+            .or(job.pixel_format)
+            .filter(|_| self.job_encodes_video(job))
+        {
+            args.extend(["-pix_fmt".into(), format.into()]);
+        }
+        if let Some(threads) = self.threads {
+            args.extend(["-threads".into(), threads.to_string().into()]);
+            // x264 sizes its own thread pool, which -threads alone doesn't always bound
+            if self.job_encodes_video(job) && actual_encoder == Some("libx264") {
+                args.extend(["-x264-params".into(), format!("threads={threads}").into()]);
+            }
+        }
+        // The first pass only analyses the video
+        if job.pass.is_some_and(|pass| pass.number == 1) {
+            args.push("-an".into());
+        }
+        if self.drop_non_av_streams {
+            args.extend(["-sn", "-dn"].map(OsString::from));
+        } else if job.effect == Effect::Reverse {
+            args.extend(["-c:s", "copy"].map(OsString::from));
+        }
+        if self.preserve_metadata {
+            args.extend(["-map_metadata", "0"].map(OsString::from));
+        }
+        if let Some(format) = job.format {
+            args.extend(["-f".into(), format.into()]);
+        }
+        args.extend(self.extra_args.iter().map(OsString::from));
+        // The first pass writes to the null device, which always exists, and existing
+        // frames of an image sequence are checked against the policy beforehand
+        let overwrite = match job.pass {
+            Some(Pass { number: 1, .. }) => "-y",
+            _ if matches!(job.effect, Effect::ImageSequence(_)) => "-y",
+            _ => self.overwrite.ffmpeg_flag(),
+        };
+        args.extend([overwrite.into(), job.output.into()]);
+        args
+    }
+
+    fn reverse_into(
+        &self,
+        job: Job,
+        progress: Option<&mut dyn FnMut(ProgressUpdate)>,
+    ) -> Result<PathBuf, VideoError> {
+        self.reverse_timed(job, progress, &mut Vec::new())
+            .map(|(path, _)| path)
+    }
+
+    /// Runs `job`, returning the output path and how long ffmpeg itself took.
+    ///
+    /// The time is zero if ffmpeg never ran (dry run or a skipped existing output).
+    /// Non-fatal problems are added to `warnings`.
+    fn reverse_timed(
+        &self,
+        job: Job,
+        progress: Option<&mut dyn FnMut(ProgressUpdate)>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(PathBuf, Duration), VideoError> {
+        let started = Instant::now();
+        let (input, output) = (job.input, job.output);
+        let result = self.run_job(job, progress, warnings);
+        self.record_metrics(input, output, started, result.as_ref().map(|_| ()));
+        result
+    }
+
+    /// Reports a reversal of `input` into `output` that began at `started` to the
+    /// metrics sink, if there is one
+    fn record_metrics(
+        &self,
+        input: &Path,
+        output: &Path,
+        started: Instant,
+        result: Result<(), &VideoError>,
+    ) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let size = |path: &Path| std::fs::metadata(path).ok().map(|metadata| metadata.len());
+        metrics.record(&ReverseMetrics {
+            input,
+            output,
+            input_size: size(input),
+            output_size: size(output).filter(|_| result.is_ok() && !self.dry_run),
+            elapsed: started.elapsed(),
+            error: result.err(),
+        });
+    }
+
+    /// The body of [`reverse_timed`](Self::reverse_timed)
+    fn run_job(
+        &self,
+        job: Job,
+        progress: Option<&mut dyn FnMut(ProgressUpdate)>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(PathBuf, Duration), VideoError> {
+        self.validate_paths(job.input, job.output)?;
+        self.validate_options(job.mode, warnings)?;
+        if self.skip_existing_output(job.output)? {
+            log::info!(
+                "Skipping {}: {} already exists",
+                job.input.display(),
+                job.output.display()
+            );
+            return Ok((job.output.to_path_buf(), Duration::ZERO));
+        }
+
+        if self.dry_run {
+            return Ok((job.output.to_path_buf(), Duration::ZERO));
+        }
+
+        log::info!(
+            "Reversing {} -> {}",
+            job.input.display(),
+            job.output.display()
+        );
+        // Check if ffmpeg is available
+        self.check_ffmpeg(warnings)?;
+        let probed = self.validate_input(job.input, job.mode, warnings)?;
+        self.check_frame_rate(&job, &probed, warnings);
+        let crop = self.crop_for(&job, &probed)?;
+        let fade = self.fade_for(&job, || self.probe_duration(job.input))?;
+        let job = Job {
+            has_audio: probed.has_audio,
+            rotation: probed.rotation,
+            crop,
+            pixel_format: self.playable_pixel_format(
+                &job,
+                probed.pixel_format.as_deref(),
+                warnings,
+            ),
+            fade,
+            ..job
+        };
+
+        let elapsed = self.execute(job, progress, warnings)?;
+        log::info!("Finished {} in {:.1?}", job.output.display(), elapsed);
+        Ok((job.output.to_path_buf(), elapsed))
+    }
+
+    /// Runs ffmpeg for `job`, whose input has been validated and probed, and returns how
+    /// long the successful run took.
+    ///
+    /// ffmpeg writes to a temp file that only replaces the output once it's complete and,
+    /// if enabled, has been decoded without errors; the frames of an image sequence are
+    /// written straight into place. Transient failures are retried.
+    fn execute(
+        &self,
+        job: Job,
+        progress: Option<&mut dyn FnMut(ProgressUpdate)>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Duration, VideoError> {
+        prepare_output_dir(job.output)?;
+        self.check_temp_dir()?;
+
+        let temp = (!matches!(job.effect, Effect::ImageSequence(_)))
+            .then(|| TempOutput::new_in(self.temp_dir.as_deref(), job.output));
+        let pass_log =
+            (self.two_pass && job.effect == Effect::Reverse && self.encodes_video(job.mode))
+                .then(|| PassLog::new(&self.scratch_dir()));
+        let ffmpeg_job = Job {
+            output: temp.as_ref().map_or(job.output, TempOutput::path),
+            pass: pass_log.as_ref().map(|log| Pass {
+                number: 2,
+                log: log.prefix(),
+            }),
+            ..job
+        };
+        let control = self.run_control();
+        let streams = control.is_limited() || control.echo_stderr;
+
+        if let Some(pass) = ffmpeg_job.pass {
+            let first_pass = Job {
+                output: Path::new(NULL_DEVICE),
+                format: Some("null"),
+                pass: Some(Pass { number: 1, ..pass }),
+                ..ffmpeg_job
+            };
+            let command = self.command_for(&first_pass, false, &mut Vec::new());
+            log::debug!("Running first pass {command}");
+            let result = self.run_ffmpeg(&command)?;
+            check_ffmpeg_output(&result, &command)?;
+        }
+
+        // Execute ffmpeg command to reverse the video
+        let command = self.command_for(&ffmpeg_job, progress.is_some(), warnings);
+        let FfmpegCommand { program, args } = &command;
+        log::debug!("Running {command}");
+
+        let mut progress = progress;
+        let total = progress.as_ref().and_then(|_| {
+            let total = match job.segment {
+                Some(segment) => Some((segment.end - segment.start).as_secs_f64()),
+                None => self.probe_duration(job.input),
+            };
+            // ffmpeg reports progress in output time, which a speed change stretches
+            // and repeating multiplies
+            total.map(|total| total * f64::from(self.repeat) / self.speed.unwrap_or(1.0))
+        });
+        let mut attempt = 0;
+        let elapsed = loop {
+            let started = Instant::now();
+            let result = match progress.as_deref_mut() {
+                Some(callback) => {
+                    let mut tracker = ProgressTracker::new(total, callback);
+                    self.runner.run_streaming(
+                        program,
+                        args,
+                        &mut |line| tracker.handle_line(line),
+                        &control,
+                    )
+                }
+                None if streams => self
+                    .runner
+                    .run_streaming(program, args, &mut |_| {}, &control),
+                None => self.runner.run(program, args),
+            }
+            .map_err(|err| self.run_error(err))?;
+            let elapsed = started.elapsed();
+
+            match check_ffmpeg_output(&result, &command) {
+                Err(err) if attempt < self.retries && is_transient(&err) => {
+                    attempt += 1;
+                    let delay = self.retry_delay * 2u32.saturating_pow(attempt - 1);
+                    log::warn!(
+                        "{}; retrying in {:?} (attempt {} of {})",
+                        err,
+                        delay,
+                        attempt,
+                        self.retries
+                    );
+                    // A partial file would trip up `-n` on the next attempt
+                    if let Some(temp) = &temp {
+                        let _ = std::fs::remove_file(temp.path());
+                    }
+                    std::thread::sleep(delay);
+                }
+                result => {
+                    result?;
+                    break elapsed;
+                }
+            }
+        };
+        if self.verify_output {
+            let result = self
+                .runner
+                .run(
+                    self.ffmpeg_path.as_os_str(),
+                    &verify_args(ffmpeg_job.output),
+                )
+                .map_err(|err| self.run_error(err))?;
+            check_decode(job.output, &result)?;
+        }
+        if let Some(temp) = temp {
+            temp.persist(job.output)?;
+        }
+        Ok(elapsed)
+    }
+
+    /// Checks the configured options that don't depend on the input
+    fn validate_options(
+        &self,
+        mode: ReverseMode,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(), VideoError> {
+        self.audio.validate(mode)?;
+        if self.speed.is_some_and(|s| !s.is_finite() || s <= 0.0) {
+            return Err(VideoError::InvalidOptions(
+                "Speed must be a positive number".to_string(),
+            ));
+        }
+        if self
+            .normalize_fps
+            .is_some_and(|fps| !fps.is_finite() || fps <= 0.0)
+        {
+            return Err(VideoError::InvalidOptions(
+                "Normalized frame rate must be a positive number".to_string(),
+            ));
+        }
+        if self.repeat == 0 {
+            return Err(VideoError::InvalidOptions(
+                "Repeat count must be at least 1".to_string(),
+            ));
+        }
+        if self
+            .aspect_crop
+            .is_some_and(|(width, height)| width == 0 || height == 0)
+        {
+            return Err(VideoError::InvalidOptions(
+                "Aspect ratio parts must be positive".to_string(),
+            ));
+        }
+        if self.threads == Some(0) {
+            return Err(VideoError::InvalidOptions(
+                "Thread count must be at least 1".to_string(),
+            ));
+        }
+        if self.quiet && self.verbose_ffmpeg {
+            return Err(VideoError::InvalidOptions(
+                "Quiet and verbose ffmpeg output can't be combined".to_string(),
+            ));
+        }
+        if self.fade == Some(Duration::ZERO) {
+            return Err(VideoError::InvalidOptions(
+                "Fade length must be positive".to_string(),
+            ));
+        }
+        if let Some(format) = &self.output_format {
+            if !OUTPUT_FORMATS.contains(&format.as_str()) {
+                return Err(VideoError::InvalidOptions(format!(
+                    "Unsupported output format '{}'; use one of: {}",
+                    format,
+                    OUTPUT_FORMATS.join(", ")
+                )));
+            }
+            if let Some(encoder) = self
+                .encoder
+                .as_deref()
+                .filter(|e| format == "webm" && !WEBM_ENCODERS.contains(e))
+            {
+                return Err(VideoError::InvalidOptions(format!(
+                    "WebM can't hold video from encoder {}; use one of: {}",
+                    encoder,
+                    WEBM_ENCODERS.join(", ")
+                )));
+            }
+        }
+        if let Some(template) = &self.output_template {
+            check_template(template)?;
+        }
+        match (self.target_bitrate, self.crf) {
+            (Some(0), _) => {
+                return Err(VideoError::InvalidOptions(
+                    "Target bitrate must be positive".to_string(),
+                ))
+            }
+            (Some(_), Some(_)) => {
+                return Err(VideoError::InvalidOptions(
+                    "A CRF and a target bitrate can't be combined".to_string(),
+                ))
+            }
+            (None, _) if self.two_pass => {
+                return Err(VideoError::InvalidOptions(
+                    "Two-pass encoding needs a target bitrate".to_string(),
+                ))
+            }
+            (Some(_), _) if !self.encodes_video(mode) => return Err(VideoError::InvalidOptions(
+                "A target bitrate has no effect when the video is copied, as in audio-only mode"
+                    .to_string(),
+            )),
+            _ => {}
+        }
+        if let Some(crf) = self.crf.filter(|crf| *crf > MAX_CRF) {
+            return Err(VideoError::InvalidOptions(format!(
+                "CRF {} is out of range; use 0-{}",
+                crf, MAX_CRF
+            )));
+        }
+        if let Some(preset) = self.preset.as_deref().filter(|p| !PRESETS.contains(p)) {
+            return Err(VideoError::InvalidOptions(format!(
+                "Unknown preset '{}'; use one of: {}",
+                preset,
+                PRESETS.join(", ")
+            )));
+        }
+        validate_video_filters(&self.video_filters)?;
+        validate_extra_args(&self.extra_args, warnings)
+    }
+
+    /// `-crf`/`-preset` for the video encode by `encoder`, dropped with a warning for
+    /// encoders other than x264/x265. If the encoder ffmpeg picks for the container isn't
+    /// known, it is assumed to support them. libvpx-vp9 always gets a CRF, with
+    /// [`DEFAULT_VP9_CRF`] when none is set, unless a target bitrate is.
+    fn quality_args(&self, encoder: Option<&str>, warnings: &mut Vec<Warning>) -> Vec<OsString> {
+        // libvpx-vp9 only encodes at constant quality with its bitrate target set to zero;
+        // otherwise it aims for a low default bitrate
+        if encoder == Some("libvpx-vp9") && self.target_bitrate.is_none() {
+            if self.preset.is_some() {
+                warn(
+                    warnings,
+                    Warning::QualityOptionsIgnored {
+                        encoder: "libvpx-vp9".to_string(),
+                    },
+                );
+            }
+            let crf = self.crf.unwrap_or(DEFAULT_VP9_CRF);
+            return ["-crf", &crf.to_string(), "-b:v", "0"]
+                .map(OsString::from)
+                .into();
+        }
+        if self.crf.is_none() && self.preset.is_none() {
+            return Vec::new();
+        }
+        if let Some(encoder) = encoder.filter(|e| !["libx264", "libx265"].contains(e)) {
+            warn(
+                warnings,
+                Warning::QualityOptionsIgnored {
+                    encoder: encoder.to_string(),
+                },
+            );
+            return Vec::new();
+        }
+        let mut args: Vec<OsString> = Vec::new();
+        if let Some(crf) = self.crf {
+            args.extend(["-crf".into(), crf.to_string().into()]);
+        }
+        if let Some(preset) = &self.preset {
+            args.extend(["-preset".into(), preset.into()]);
+        }
+        args
+    }
+
+    /// Whether a plain reversal in `mode` re-encodes the video rather than copying it
+    fn encodes_video(&self, mode: ReverseMode) -> bool {
+        mode.reverses_video() || self.speed.is_some() || self.repeat > 1
+    }
+
+    /// Whether `job` encodes video with the configured encoder, as opposed to copying it
+    /// or writing images
+    fn job_encodes_video(&self, job: &Job) -> bool {
+        match job.effect {
+            Effect::Reverse => self.encodes_video(job.mode),
+            Effect::Gif(_)
+            | Effect::Thumbnail(_)
+            | Effect::ImageSequence(_)
+            | Effect::AudioExtract => false,
+            Effect::SideBySide | Effect::Boomerang(_) => true,
+        }
+    }
+
+    /// [`COMPATIBLE_PIXEL_FORMAT`] if `job` writes H.264 to an MP4 or MOV from a source
+    /// whose pixel format wouldn't play everywhere and no format is configured
+    fn playable_pixel_format(
+        &self,
+        job: &Job,
+        source: Option<&str>,
+        warnings: &mut Vec<Warning>,
+    ) -> Option<&'static str> {
+        let source = source.filter(|format| !PLAYABLE_PIXEL_FORMATS.contains(format))?;
+        // ffmpeg picks libx264 for these containers when no encoder is given
+        let h264 = self
+            .encoder
+            .as_deref()
+            .is_none_or(|encoder| encoder.contains("264"));
+        let mp4 = job.output.extension().is_some_and(|ext| {
+            ["mp4", "m4v", "mov"]
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        });
+        if self.pixel_format.is_some() || !h264 || !mp4 || !self.job_encodes_video(job) {
+            return None;
+        }
+        warn(
+            warnings,
+            Warning::PixelFormatConverted {
+                from: source.to_string(),
+            },
+        );
+        Some(COMPATIBLE_PIXEL_FORMAT)
+    }
+
+    /// `-c:v` for the configured encoder followed by the quality options of
+    /// `actual_encoder`, the one ffmpeg will use
+    fn video_encode_args(
+        &self,
+        encoder: Option<&str>,
+        actual_encoder: Option<&str>,
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        if let Some(encoder) = encoder {
+            args.extend(["-c:v".into(), encoder.into()]);
+        }
+        args.extend(self.quality_args(actual_encoder, warnings));
+        args.extend(self.bitrate_args());
+        args
+    }
+
+    /// `-b:v` for the target bitrate, if one is set
+    fn bitrate_args(&self) -> Vec<OsString> {
+        match self.target_bitrate {
+            Some(kbps) => vec!["-b:v".into(), format!("{kbps}k").into()],
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether the cancel token has been set
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// The limits a configured timeout and cancel token put on each ffmpeg run
+    fn run_control(&self) -> RunControl {
+        RunControl {
+            timeout: self.timeout,
+            cancel: self.cancel.clone(),
+            echo_stderr: self.verbose_ffmpeg,
+        }
+    }
+
+    /// Runs `command` under [`run_control`](Self::run_control), streaming it only when
+    /// it may have to be stopped early or its log echoed
+    fn run_ffmpeg(&self, command: &FfmpegCommand) -> Result<std::process::Output, VideoError> {
+        let control = self.run_control();
+        let FfmpegCommand { program, args } = command;
+        if control.is_limited() || control.echo_stderr {
+            self.runner
+                .run_streaming(program, args, &mut |_| {}, &control)
+        } else {
+            self.runner.run(program, args)
+        }
+        .map_err(|err| self.run_error(err))
+    }
+
+    /// Maps an error from running ffmpeg, turning an expired timeout into
+    /// [`VideoError::Timeout`] and a cancellation into [`VideoError::Cancelled`]
+    fn run_error(&self, err: std::io::Error) -> VideoError {
+        match (err.kind(), self.timeout) {
+            (std::io::ErrorKind::TimedOut, Some(timeout)) => VideoError::Timeout(timeout),
+            (std::io::ErrorKind::Interrupted, _) if self.cancel.is_some() => VideoError::Cancelled,
+            _ => VideoError::IoError(err),
+        }
+    }
+
+    /// Applies the overwrite policy to `output_path`; `Ok(true)` means the job should be skipped
+    fn skip_existing_output(&self, output_path: &Path) -> Result<bool, VideoError> {
+        if !output_path.exists() {
+            return Ok(false);
+        }
+        match self.overwrite {
+            OverwritePolicy::Always => Ok(false),
+            OverwritePolicy::Never => Err(VideoError::OutputExists(output_path.to_path_buf())),
+            OverwritePolicy::Skip => Ok(true),
+        }
+    }
+
+    /// Whether the file's extension is in the allow-list, ignoring ASCII case.
+    ///
+    /// The bytes are compared as they are, so names that aren't valid UTF-8 still match.
+    fn is_allowed_extension(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| {
+            self.allowed_extensions
+                .iter()
+                .any(|allowed| ext.eq_ignore_ascii_case(allowed))
+        })
+    }
+
+    /// Checks the input/output pair before anything is spawned
+    fn validate_paths(&self, input_path: &Path, output_path: &Path) -> Result<(), VideoError> {
+        // Validate input file
+        if !input_path.exists() {
+            return Err(VideoError::InvalidInput(
+                "Input file does not exist".to_string(),
+            ));
+        }
+        // A broken download often leaves an empty file, which ffmpeg reports obscurely;
+        // truncated but non-empty files are caught by the ffprobe check
+        if std::fs::metadata(input_path).is_ok_and(|metadata| metadata.len() == 0) {
+            return Err(VideoError::InvalidInput("Input file is empty".to_string()));
+        }
+
+        // Check file extension
+        if !self.is_allowed_extension(input_path) {
+            return Err(VideoError::InvalidInput(format!(
+                "Unsupported input file type (expected one of: {})",
+                self.allowed_extensions.join(", ")
+            )));
+        }
+
+        // Replacing the input is only safe because of the temp-file rename, and is
+        // almost always a mistake, so it needs an explicit opt-in
+        if !self.allow_in_place && is_same_file(input_path, output_path) {
+            return Err(VideoError::InvalidInput(
+                "Output path must differ from the input path".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Async variant of `check_ffmpeg`, sharing its cache
+    #[cfg(feature = "tokio")]
+    async fn check_ffmpeg_async(&self, warnings: &mut Vec<Warning>) -> Result<(), VideoError> {
+        if let Some(checked) = self.ffmpeg_checked.get() {
+            warnings.extend(checked.iter().cloned());
+            return Ok(());
+        }
+        let mut found = Vec::new();
+        self.run_ffmpeg_check_async(&mut found).await?;
+        warnings.extend(self.ffmpeg_checked.get_or_init(|| found).iter().cloned());
+        Ok(())
+    }
+
+    /// Runs `command` to completion under [`run_control`](Self::run_control), killing it
+    /// once the timeout expires or the cancel token is set
+    #[cfg(feature = "tokio")]
+    async fn output_async(
+        &self,
+        command: &mut tokio::process::Command,
+    ) -> Result<std::process::Output, VideoError> {
+        let control = self.run_control();
+        let deadline = control.timeout.map(|timeout| Instant::now() + timeout);
+        // Dropping the unfinished run kills the child
+        let mut run = std::pin::pin!(command.stdin(Stdio::null()).kill_on_drop(true).output());
+        loop {
+            if let Some(err) = control.interruption(deadline) {
+                return Err(self.run_error(err));
+            }
+            match control.next_wait(deadline) {
+                Some(wait) => {
+                    if let Ok(result) = tokio::time::timeout(wait, &mut run).await {
+                        return Ok(result?);
+                    }
+                }
+                None => return Ok(run.await?),
+            }
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn run_ffmpeg_check_async(&self, warnings: &mut Vec<Warning>) -> Result<(), VideoError> {
+        match tokio::process::Command::new(&self.ffmpeg_path)
+            .arg("-version")
+            .output()
+            .await
+        {
+            Ok(output) => self.check_version(&output, warnings),
+            Err(_) => Err(VideoError::FFmpegNotFound),
+        }?;
+        if let Some(encoder) = &self.encoder {
+            let output = tokio::process::Command::new(&self.ffmpeg_path)
+                .args(encoders_args())
+                .output()
+                .await
+                .map_err(|_| VideoError::FFmpegNotFound)?;
+            check_encoder_listed(encoder, &output)?;
+        }
+        if let Some(format) = &self.output_format {
+            let supported = match self.output_formats.get() {
+                Some(formats) => formats,
+                None => {
+                    let output = tokio::process::Command::new(&self.ffmpeg_path)
+                        .args(formats_args())
+                        .output()
+                        .await
+                        .map_err(|_| VideoError::FFmpegNotFound)?;
+                    let formats = muxable_output_formats(&String::from_utf8_lossy(&output.stdout));
+                    self.output_formats.get_or_init(|| formats)
+                }
+            };
+            check_output_format(format, supported)?;
+        }
+        Ok(())
+    }
+
+    /// Async variant of [`reverse_video`](Self::reverse_video) using `tokio::process`.
+    ///
+    /// ffmpeg is spawned directly with tokio rather than through the configured
+    /// [`CommandRunner`], so no worker thread is blocked while it runs. The
+    /// [`timeout`](VideoReverserBuilder::timeout) and
+    /// [`cancel_token`](VideoReverserBuilder::cancel_token) stop it as they do a
+    /// synchronous run.
+    #[cfg(feature = "tokio")]
+    pub async fn reverse_video_async<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+    ) -> Result<PathBuf, VideoError> {
+        let started = Instant::now();
+        let input_path = input_path.as_ref();
+        let output_path = self.generate_output_filename(input_path);
+        let result = self.run_async(input_path, &output_path).await;
+        self.record_metrics(
+            input_path,
+            &output_path,
+            started,
+            result.as_ref().map(|_| ()),
+        );
+        result
+    }
+
+    /// The body of [`reverse_video_async`](Self::reverse_video_async)
+    #[cfg(feature = "tokio")]
+    async fn run_async(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+    ) -> Result<PathBuf, VideoError> {
+        let output_path = output_path.to_path_buf();
+
+        let warnings = &mut Vec::new();
+        self.validate_paths(input_path, &output_path)?;
+        self.validate_options(self.mode, warnings)?;
+        if self.skip_existing_output(&output_path)? || self.dry_run {
+            return Ok(output_path);
+        }
+        log::info!(
+            "Reversing {} -> {}",
+            input_path.display(),
+            output_path.display()
+        );
+        self.check_ffmpeg_async(warnings).await?;
+        let probed = if self.probe_validation {
+            match tokio::process::Command::new(&self.ffprobe_path)
+                .args(stream_probe_args(input_path))
+                .stdin(Stdio::null())
+                .output()
+                .await
+            {
+                Ok(output) => check_stream_probe(
+                    input_path,
+                    &output,
+                    self.mode,
+                    (self.video_stream, self.audio_stream),
+                    warnings,
+                )?,
+                Err(_) => {
+                    warn(warnings, Warning::FFprobeUnavailable);
+                    ProbedInput::ASSUMED
+                }
+            }
+        } else {
+            ProbedInput::ASSUMED
+        };
+
+        let dir = output_dir(&output_path);
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|err| not_writable(dir, err))?;
+        let probe = TempOutput::new(&output_path);
+        tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(probe.path())
+            .await
+            .map_err(|err| not_writable(dir, err))?;
+        drop(probe);
+
+        let encoding = if self.match_source_encoding {
+            let probe = tokio::process::Command::new(&self.ffprobe_path)
+                .args(SourceEncoding::ffprobe_args(input_path))
+                .stdin(Stdio::null())
+                .output()
+                .await
+                .ok()
+                .filter(|output| output.status.success());
+            source_encoding_from_probe(probe, warnings)
+        } else {
+            None
+        };
+
+        let duration = match self.fade {
+            Some(_) => tokio::process::Command::new(&self.ffprobe_path)
+                .args(duration_probe_args(input_path))
+                .stdin(Stdio::null())
+                .output()
+                .await
+                .ok()
+                .and_then(|output| parse_duration_probe(&output)),
+            None => None,
+        };
+        if let Some(dir) = &self.temp_dir {
+            let probe = TempOutput::new_in(Some(dir), &output_path);
+            tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(probe.path())
+                .await
+                .map_err(|err| unusable_temp_dir(dir, err))?;
+        }
+        let temp = TempOutput::new_in(self.temp_dir.as_deref(), &output_path);
+        let job = Job {
+            has_audio: probed.has_audio,
+            rotation: probed.rotation,
+            ..Job::new(input_path, temp.path(), self.mode)
+        };
+        self.check_frame_rate(&job, &probed, warnings);
+        let job = Job {
+            crop: self.crop_for(&job, &probed)?,
+            pixel_format: self.playable_pixel_format(
+                &job,
+                probed.pixel_format.as_deref(),
+                warnings,
+            ),
+            ..job
+        };
+        let job = Job {
+            fade: self.fade_for(&job, || duration)?,
+            ..job
+        };
+        let command =
+            self.ffmpeg_command(self.ffmpeg_args(&job, false, encoding.as_ref(), warnings));
+        log::debug!("Running {command}");
+        let mut run = tokio::process::Command::new(&command.program);
+        run.args(&command.args);
+        let result = self.output_async(&mut run).await?;
+
+        check_ffmpeg_output(&result, &command)?;
+        if self.verify_output {
+            let mut verify = tokio::process::Command::new(&self.ffmpeg_path);
+            verify.args(verify_args(temp.path()));
+            let result = self.output_async(&mut verify).await?;
+            check_decode(&output_path, &result)?;
+        }
+        // Like `TempOutput::persist`
+        match tokio::fs::rename(temp.path(), &output_path).await {
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+                let sibling = TempOutput::new(&output_path);
+                tokio::fs::copy(temp.path(), sibling.path()).await?;
+                tokio::fs::rename(sibling.path(), &output_path).await?;
+            }
+            result => result?,
+        }
+        log::info!("Finished {}", output_path.display());
+        Ok(output_path)
+    }
+}
+
+/// ffprobe arguments that list every stream of the input
+fn stream_probe_args(input_path: &Path) -> Vec<OsString> {
+    vec![
+        "-v".into(),
+        "error".into(),
+        "-show_streams".into(),
+        input_path.into(),
+    ]
+}
+
+/// The error for an input ffprobe failed on
+fn unreadable_input(input_path: &Path, output: &std::process::Output) -> VideoError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    VideoError::NotAVideo(format!(
+        "{}: {}",
+        input_path.display(),
+        last_meaningful_line(&stderr).unwrap_or("ffprobe could not read the file")
+    ))
+}
+
+/// Rejects inputs that ffprobe can't read or that have no video stream, or no audio
+/// stream when `mode` reverses only the audio, and `(video, audio)` stream selections
+/// that don't name a stream of that type. Returns whether there is audio and the
+/// rotation of the selected video stream.
+fn check_stream_probe(
+    input_path: &Path,
+    output: &std::process::Output,
+    mode: ReverseMode,
+    selected: (Option<usize>, Option<usize>),
+    warnings: &mut Vec<Warning>,
+) -> Result<ProbedInput, VideoError> {
+    if !output.status.success() {
+        return Err(unreadable_input(input_path, output));
+    }
+    let streams = StreamInfo::parse_all(&String::from_utf8_lossy(&output.stdout));
+    for (index, codec_type) in [(selected.0, "video"), (selected.1, "audio")] {
+        let Some(index) = index else { continue };
+        match streams.iter().find(|stream| stream.index == index) {
+            Some(stream) if stream.codec_type == codec_type => {}
+            Some(stream) => {
+                return Err(VideoError::InvalidInput(format!(
+                    "Stream {index} of {} is {}, not {codec_type}",
+                    input_path.display(),
+                    stream.codec_type
+                )))
+            }
+            None => {
+                return Err(VideoError::InvalidInput(format!(
+                    "{} has no stream {index}",
+                    input_path.display()
+                )))
+            }
+        }
+    }
+    let has_stream = |codec_type: &str| streams.iter().any(|s| s.codec_type == codec_type);
+    let Some(video) = streams.iter().find(|stream| match selected.0 {
+        Some(index) => stream.index == index,
+        None => stream.codec_type == "video",
+    }) else {
+        return Err(VideoError::NotAVideo(format!(
+            "{} contains no video stream",
+            input_path.display()
+        )));
+    };
+    let has_audio = has_stream("audio");
+    if !has_audio {
+        if mode == ReverseMode::AudioOnly {
+            return Err(VideoError::NoAudioStream(input_path.to_path_buf()));
+        }
+        warn(warnings, Warning::NoAudioStream);
+    }
+    Ok(ProbedInput {
+        has_audio,
+        rotation: video.rotation,
+        variable_frame_rate: video.variable_frame_rate,
+        pixel_format: video.pixel_format.clone(),
+        dimensions: video.width.zip(video.height),
+    })
+}
+
+/// Parses a successful ffprobe run, warning when there is nothing to use
+fn source_encoding_from_probe(
+    output: Option<std::process::Output>,
+    warnings: &mut Vec<Warning>,
+) -> Option<SourceEncoding> {
+    match output {
+        Some(output) => Some(SourceEncoding::parse(&String::from_utf8_lossy(
+            &output.stdout,
+        ))),
+        None => {
+            warn(warnings, Warning::SourceEncodingUnknown);
+            None
+        }
+    }
+}
+
+/// Rejects output templates with unknown placeholders or unbalanced braces
+fn check_template(template: &str) -> Result<(), VideoError> {
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        let placeholder = rest[start..]
+            .strip_prefix('{')
+            .and_then(|tail| tail.split_once('}'))
+            .map(|(placeholder, _)| placeholder)
+            .filter(|placeholder| !placeholder.contains('{'));
+        let Some(placeholder) = placeholder else {
+            return Err(VideoError::InvalidOptions(format!(
+                "Unbalanced braces in output template '{template}'"
+            )));
+        };
+        if !TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+            return Err(VideoError::InvalidOptions(format!(
+                "Unknown placeholder {{{placeholder}}} in output template; use one of: {}",
+                TEMPLATE_PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{p}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        rest = &rest[start + placeholder.len() + 2..];
+    }
+    Ok(())
+}
+
+/// Whether `name` could have been rendered from `template`, which [`check_template`]
+/// accepts. A template of only `{stem}`, `{parent}` and `{ext}` placeholders fits any
+/// file name, so no name is taken to match it.
+fn matches_template(template: &str, name: &str) -> bool {
+    // Literal text and placeholder names, in order
+    let mut parts: Vec<(bool, String)> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        parts.push((false, rest[..start].replace(['/', '\\'], "_")));
+        parts.push((true, rest[start + 1..start + len].to_string()));
+        rest = &rest[start + len + 1..];
+    }
+    parts.push((false, rest.replace(['/', '\\'], "_")));
+    let distinctive = parts.iter().any(|(placeholder, text)| match placeholder {
+        true => !["stem", "parent", "ext"].contains(&text.as_str()),
+        false => !text.is_empty() && text != ".",
+    });
+    distinctive && fits_template(&parts, name)
+}
+
+/// Whether `name` splits into values for the placeholders of `parts` around its literal
+/// text, for [`matches_template`]
+fn fits_template(parts: &[(bool, String)], name: &str) -> bool {
+    let Some(((placeholder, text), rest)) = parts.split_first() else {
+        return name.is_empty();
+    };
+    if !placeholder {
+        return name
+            .strip_prefix(text.as_str())
+            .is_some_and(|name| fits_template(rest, name));
+    }
+    let digits = |value: &str| value.bytes().all(|b| b.is_ascii_digit());
+    let fits = |value: &str| match text.as_str() {
+        "date" => {
+            value.len() == 10
+                && value.bytes().enumerate().all(|(i, b)| match i {
+                    4 | 7 => b == b'-',
+                    _ => b.is_ascii_digit(),
+                })
+        }
+        "index" => digits(value),
+        "duration" => value == "unknown" || digits(value),
+        _ => true,
+    };
+    name.char_indices()
+        .map(|(at, c)| at + c.len_utf8())
+        .any(|end| fits(&name[..end]) && fits_template(rest, &name[end..]))
+}
+
+/// Makes a rendered template safe to use as a single file name in the input's directory
+fn sanitize_file_name(name: &str) -> String {
+    let name = name.replace(['/', '\\'], "_");
+    match name.as_str() {
+        "" | "." | ".." => "_".repeat(name.len().max(1)),
+        _ => name,
+    }
+}
+
+/// Formats `time` as a `YYYY-MM-DD` date in UTC
+fn utc_date(time: std::time::SystemTime) -> String {
+    let days = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+    // Converts days since 1970-01-01 to a civil date, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Reads the frame times that `showinfo` logs after a scene `select`, e.g.
+/// `[Parsed_showinfo_1 @ 0x55d0] n:   0 pts:  51200 pts_time:4.16667 duration: ...`
+fn parse_scene_cuts(stderr: &str) -> Vec<Duration> {
+    stderr
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| line.split_once("pts_time:"))
+        .filter_map(|(_, rest)| rest.split_whitespace().next()?.parse::<f64>().ok())
+        .filter_map(|secs| Duration::try_from_secs_f64(secs).ok())
+        .collect()
+}
+
+/// Resolves a binary from its environment override, falling back to `PATH` lookup of `name`
+fn default_tool_path(env_value: Option<OsString>, name: &str) -> PathBuf {
+    env_value
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// The input with symlinks and `..` components resolved, so generated output names
+/// land next to the real file rather than next to a link to it.
+///
+/// The result is absolute. Paths that can't be resolved, such as missing files, are
+/// returned unchanged; validation rejects them later.
+fn resolve_input(input_path: &Path) -> PathBuf {
+    input_path
+        .canonicalize()
+        .unwrap_or_else(|_| input_path.to_path_buf())
+}
+
+/// Builds `<stem><suffix>.<ext>` next to the input
+///
+/// Only the text after the last dot counts as the extension, so `archive.tar.mp4` becomes
+/// `archive.tar-rev.mp4`. Hidden files such as `.mp4` have no extension and keep their
+/// leading dot (`.mp4-rev`), and a trailing dot (`movie.`) is dropped (`movie-rev`).
+fn output_filename_with_suffix(input_path: &Path, suffix: &str) -> PathBuf {
+    let mut new_name = input_path.file_stem().unwrap_or_default().to_os_string();
+    new_name.push(suffix);
+    // Pushing the extension by hand keeps dots in the stem or suffix intact
+    if let Some(extension) = input_path.extension().filter(|ext| !ext.is_empty()) {
+        new_name.push(".");
+        new_name.push(extension);
+    }
+    input_path.with_file_name(new_name)
+}
+
+/// A conservative default for parallel batches: half the available cores, at least one
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| (n.get() / 2).max(1))
+        .unwrap_or(1)
+}
+
+/// Extracts the message from a caught panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Where the first pass of a two-pass encode writes its discarded output
+#[cfg(windows)]
+const NULL_DEVICE: &str = "NUL";
+#[cfg(not(windows))]
+const NULL_DEVICE: &str = "/dev/null";
+
+/// Statistics files of a two-pass encode, removed when dropped.
+///
+/// ffmpeg names them after a prefix, e.g. `<prefix>-0.log` and `<prefix>-0.log.mbtree`
+/// for libx264; the prefix is unique within the directory.
+struct PassLog {
+    prefix: PathBuf,
+}
+
+impl PassLog {
+    fn new(dir: &Path) -> Self {
+        Self {
+            prefix: dir.join(format!(
+                "mdmp4rev-pass-{}-{}",
+                std::process::id(),
+                TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            )),
+        }
+    }
+
+    fn prefix(&self) -> &Path {
+        &self.prefix
+    }
+}
+
+impl Drop for PassLog {
+    fn drop(&mut self) {
+        let (Some(dir), Some(name)) = (self.prefix.parent(), self.prefix.file_name()) else {
+            return;
+        };
+        let mut start = name.to_os_string();
+        start.push("-");
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry
+                .file_name()
+                .as_encoded_bytes()
+                .starts_with(start.as_encoded_bytes())
+            {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Distinguishes temp files created concurrently by the same process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file next to the final output that is removed unless persisted.
+///
+/// Living in the same directory keeps the final rename atomic, so the output path
+/// either doesn't exist or holds a complete file.
+struct TempOutput {
+    path: PathBuf,
+}
+
+impl TempOutput {
+    /// Picks a hidden, unique sibling of `output` that keeps its extension,
+    /// so ffmpeg still infers the container from the name
+    fn new(output: &Path) -> Self {
+        Self {
+            path: output.with_file_name(Self::name(output)),
+        }
+    }
+
+    /// Like [`new`](Self::new), but in `dir` instead of next to `output` if given
+    fn new_in(dir: Option<&Path>, output: &Path) -> Self {
+        match dir {
+            Some(dir) => Self {
+                path: dir.join(Self::name(output)),
+            },
+            None => Self::new(output),
+        }
+    }
+
+    fn name(output: &Path) -> OsString {
+        let mut name = OsString::from(".");
+        name.push(output.file_stem().unwrap_or_default());
+        name.push(format!(
+            ".tmp-{}-{}",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Some(extension) = output.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        name
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the finished file into place. A file on another file system is first
+    /// copied next to the destination, so that the last step is still a rename.
+    fn persist(self, destination: &Path) -> std::io::Result<()> {
+        match std::fs::rename(&self.path, destination) {
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+                let sibling = TempOutput::new(destination);
+                std::fs::copy(&self.path, sibling.path())?;
+                std::fs::rename(sibling.path(), destination)
+            }
+            result => result,
+        }
+    }
+}
+
+impl Drop for TempOutput {
+    fn drop(&mut self) {
+        // Still present only if we never persisted it; a missing file is fine
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether both paths name the same file, including through symlinks, `..` or hard links
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(a), Ok(b)) = (std::fs::metadata(a), std::fs::metadata(b)) {
+            return a.dev() == b.dev() && a.ino() == b.ino();
+        }
+    }
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Creates the output's parent directory if it doesn't exist yet and checks that
+/// files can be created in it, so a read-only destination fails before ffmpeg runs
+fn prepare_output_dir(output_path: &Path) -> Result<(), VideoError> {
+    let dir = output_dir(output_path);
+    std::fs::create_dir_all(dir).map_err(|err| not_writable(dir, err))?;
+    // Dropping the probe removes it again
+    let probe = TempOutput::new(output_path);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(probe.path())
+        .map_err(|err| not_writable(dir, err))?;
+    Ok(())
+}
+
+/// The error for a configured temp directory that files can't be created in
+fn unusable_temp_dir(dir: &Path, err: std::io::Error) -> VideoError {
+    VideoError::InvalidOptions(format!(
+        "Temp directory {} can't be used: {err}",
+        dir.display()
+    ))
+}
+
+/// The directory the output is written to
+fn output_dir(output_path: &Path) -> &Path {
+    output_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Reports permission problems in the output directory as [`VideoError::OutputNotWritable`]
+fn not_writable(dir: &Path, err: std::io::Error) -> VideoError {
+    match err.kind() {
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => {
+            VideoError::OutputNotWritable(dir.to_path_buf())
+        }
+        _ => VideoError::IoError(err),
+    }
+}
+
+/// Confirms `-version` output comes from ffmpeg, describing the tool otherwise
+fn identify_ffmpeg(stdout: &str) -> Result<(), String> {
+    let first_line = stdout.lines().next().unwrap_or_default().trim();
+    if first_line.starts_with("ffmpeg version") {
+        Ok(())
+    } else if first_line.starts_with("avconv version") {
+        Err("it is avconv from libav, whose options differ".to_string())
+    } else if first_line.is_empty() {
+        Err("-version printed nothing".to_string())
+    } else {
+        Err(format!("-version printed {first_line:?}"))
+    }
+}
+
+/// Reads `(major, minor)` from the first line of `ffmpeg -version`.
+///
+/// Handles release builds (`ffmpeg version 6.1.1`), tagged git builds (`n6.1`) and
+/// distro suffixes (`4.4.2-0ubuntu0.22.04.1`); a missing minor version counts as 0.
+fn parse_ffmpeg_version(stdout: &str) -> Option<(u32, u32)> {
+    let first_line = stdout.lines().next()?;
+    let mut words = first_line.split_whitespace();
+    words.find(|word| *word == "version")?;
+    let version = words.next()?;
+    let version = version.strip_prefix('n').unwrap_or(version);
+    // Splits off the leading run of digits as a number
+    fn number(text: &str) -> Option<(u32, &str)> {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        Some((text[..end].parse().ok()?, &text[end..]))
+    }
+    let (major, rest) = number(version)?;
+    let minor = match rest.strip_prefix('.') {
+        Some(rest) => number(rest)?.0,
+        None => 0,
+    };
+    Some((major, minor))
+}
+
+/// ffmpeg arguments that list the available encoders
+fn encoders_args() -> Vec<OsString> {
+    vec!["-hide_banner".into(), "-encoders".into()]
+}
+
+/// Fails unless `encoder` appears in the output of [`encoders_args`].
+///
+/// Each encoder line looks like ` V....D h264_nvenc   NVIDIA NVENC H.264 encoder`.
+fn check_encoder_listed(encoder: &str, output: &std::process::Output) -> Result<(), VideoError> {
+    let listed = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .any(|name| name == encoder);
+    if listed {
+        Ok(())
+    } else {
+        Err(VideoError::InvalidInput(format!(
+            "Encoder '{}' is not available in this ffmpeg build; `ffmpeg -encoders` lists the supported ones",
+            encoder
+        )))
+    }
+}
+
+/// ffmpeg arguments that list the container formats of the build
+fn formats_args() -> Vec<OsString> {
+    vec!["-hide_banner".into(), "-formats".into()]
+}
+
+/// Picks the [`OUTPUT_FORMATS`] whose muxer appears in the output of [`formats_args`].
+///
+/// After a legend ending in `--`, each line holds flags and comma-separated names, like
+/// ` DE matroska,webm   Matroska / WebM`; an `E` flag means ffmpeg can write it. Newer
+/// builds add a separate `d` flag for devices.
+fn muxable_output_formats(stdout: &str) -> Vec<String> {
+    let is_flags = |field: &str| field.chars().all(|c| "DEd.".contains(c));
+    let muxers: Vec<&str> = stdout
+        .lines()
+        .skip_while(|line| line.trim() != "--")
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().peekable();
+            let mut muxable = false;
+            while let Some(flags) = fields.next_if(|field| is_flags(field)) {
+                muxable |= flags.contains('E');
+            }
+            fields.next().filter(|_| muxable)
+        })
+        .flat_map(|names| names.split(','))
+        .collect();
+    OUTPUT_FORMATS
+        .iter()
+        .filter(|extension| {
+            let muxer = match **extension {
+                "mkv" => "matroska",
+                extension => extension,
+            };
+            muxers.contains(&muxer)
+        })
+        .map(|extension| extension.to_string())
+        .collect()
+}
+
+/// Fails unless the requested output format is among the `supported` ones
+fn check_output_format(format: &str, supported: &[String]) -> Result<(), VideoError> {
+    if supported.iter().any(|s| s == format) {
+        Ok(())
+    } else {
+        Err(VideoError::InvalidInput(format!(
+            "This ffmpeg build can't write {} files; it supports: {}",
+            format,
+            supported.join(", ")
+        )))
+    }
+}
+
+/// The `-hwaccel` method that decodes on the same device as a hardware encoder
+fn hwaccel_for(encoder: &str) -> Option<&'static str> {
+    let (_, family) = encoder.rsplit_once('_')?;
+    match family {
+        "nvenc" => Some("cuda"),
+        "videotoolbox" => Some("videotoolbox"),
+        "qsv" => Some("qsv"),
+        _ => None,
+    }
+}
+
+/// Rejects custom video filters that are empty or would break out of the filter chain
+/// with a `;` or a `[label]`
+fn validate_video_filters(filters: &[String]) -> Result<(), VideoError> {
+    for filter in filters {
+        if filter.trim().is_empty() {
+            return Err(VideoError::InvalidOptions(
+                "Video filters can't be empty".to_string(),
+            ));
+        }
+        if filter.contains([';', '[', ']']) {
+            return Err(VideoError::InvalidOptions(format!(
+                "Video filter '{filter}' can't contain ';', '[' or ']'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects extra arguments that would add an input, and warns about likely extra outputs.
+///
+/// A bare word in flag position usually means an additional output file; ffmpeg would
+/// write it without the temp-file and overwrite handling, but it may be intentional.
+fn validate_extra_args(
+    extra_args: &[String],
+    warnings: &mut Vec<Warning>,
+) -> Result<(), VideoError> {
+    if extra_args.iter().any(|arg| arg == "-i") {
+        return Err(VideoError::InvalidOptions(
+            "Extra ffmpeg arguments can't add inputs with -i".to_string(),
+        ));
+    }
+    let mut expects_value = false;
+    for arg in extra_args {
+        if arg.starts_with('-') {
+            expects_value = true;
+        } else if expects_value {
+            expects_value = false;
+        } else {
+            warn(warnings, Warning::SuspiciousExtraArgument(arg.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// An ffmpeg concat demuxer list naming `paths` in order. The list is text, so a path
+/// that isn't valid UTF-8 fails with [`VideoError::InvalidInput`].
+fn concat_list<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<String, VideoError> {
+    let mut list = String::new();
+    for path in paths {
+        let path = path.as_ref();
+        let Some(name) = path.to_str() else {
+            return Err(VideoError::InvalidInput(format!(
+                "{} can't be concatenated: its path isn't valid UTF-8",
+                path.display()
+            )));
+        };
+        list.push_str(&format!("file '{}'\n", name.replace('\'', r"'\''")));
+    }
+    Ok(list)
+}
+
+/// ffmpeg arguments that decode every stream of `path` and discard the result, logging
+/// only errors
+fn verify_args(path: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["-v".into(), "error".into(), "-i".into(), path.into()];
+    args.extend(["-f", "null", "-"].map(OsString::from));
+    args
+}
+
+/// Fails if the decode run of [`verify_args`] exited badly or logged any error
+fn check_decode(output_path: &Path, result: &std::process::Output) -> Result<(), VideoError> {
+    let log = String::from_utf8_lossy(&result.stderr);
+    if result.status.success() && log.trim().is_empty() {
+        return Ok(());
+    }
+    Err(VideoError::OutputCorrupt {
+        path: output_path.to_path_buf(),
+        log: log.into_owned(),
+    })
+}
+
+/// Whether a failure may go away on its own, so running ffmpeg again could succeed
+fn is_transient(err: &VideoError) -> bool {
+    matches!(
+        err,
+        VideoError::ProcessingError {
+            kind: FailureKind::Other,
+            ..
+        }
+    )
+}
+
+/// ffprobe arguments that print the container duration in seconds
+fn duration_probe_args(path: &Path) -> Vec<OsString> {
+    vec![
+        "-v".into(),
+        "error".into(),
+        "-show_entries".into(),
+        "format=duration".into(),
+        "-of".into(),
+        "default=noprint_wrappers=1:nokey=1".into(),
+        path.into(),
+    ]
+}
+
+/// The duration printed by a successful run with [`duration_probe_args`]
+fn parse_duration_probe(output: &std::process::Output) -> Option<f64> {
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Maps a failed ffmpeg exit status to a `ProcessingError`
+fn check_ffmpeg_output(
+    result: &std::process::Output,
+    command: &FfmpegCommand,
+) -> Result<(), VideoError> {
+    if result.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+    let message = last_meaningful_line(&stderr)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("ffmpeg exited with {}", result.status));
+    Err(VideoError::ProcessingError {
+        kind: FailureKind::classify(&stderr),
+        code: result.status.code(),
+        message,
+        stderr,
+        command: command.to_string(),
+    })
+}
+
+/// Picks the line of an ffmpeg log that best explains a failure.
+///
+/// ffmpeg usually ends with a generic "Conversion failed!", so the last line
+/// that isn't one of those summaries is preferred.
+fn last_meaningful_line(stderr: &str) -> Option<&str> {
+    const GENERIC: &[&str] = &["Conversion failed!", "Exiting normally, received signal 2."];
+    let mut lines = stderr.lines().map(str::trim).filter(|l| !l.is_empty());
+    let last = lines.clone().next_back();
+    lines.rfind(|l| !GENERIC.contains(l)).or(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    // Temp directories under the resolved temp path, so expected output paths match the
+    // canonical ones the reverser derives even where the temp dir is a symlink (macOS)
+    fn tempdir() -> std::io::Result<TempDir> {
+        tempfile::tempdir_in(std::env::temp_dir().canonicalize()?)
+    }
+
+    type CallLog = Arc<Mutex<Vec<(String, Vec<String>)>>>;
+    type Behavior =
+        Arc<dyn Fn(&str, &[&str]) -> std::io::Result<std::process::Output> + Send + Sync>;
+
+    // Mock runner for testing
+    struct MockCommandRunner {
+        // We use a Mutex to allow interior mutability for tracking calls
+        calls: CallLog,
+        // Closures to determine behavior based on command
+        behavior: Behavior,
+    }
+
+    impl CommandRunner for MockCommandRunner {
+        fn run(&self, program: &OsStr, args: &[OsString]) -> std::io::Result<std::process::Output> {
+            let output_path = args.last().map(PathBuf::from);
+            let program = program.to_string_lossy().into_owned();
+            let args: Vec<String> = args
+                .iter()
+                .map(|s| s.to_string_lossy().into_owned())
+                .collect();
+            self.calls
+                .lock()
+                .unwrap()
+                .push((program.clone(), args.clone()));
+            let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+            let result = (self.behavior)(&program, &arg_refs);
+
+            // Like ffmpeg, leave a file at the output path (the last argument) on success
+            if let (Ok(output), Some(path)) = (&result, output_path) {
+                let writes_output = program != "ffprobe"
+                    && path.is_absolute()
+                    && path.parent().is_some_and(Path::is_dir);
+                if output.status.success() && writes_output {
+                    fs::write(path, "reversed")?;
+                }
+            }
+            result
+        }
+    }
+
+    impl MockCommandRunner {
+        fn new(
+            behavior: impl Fn(&str, &[&str]) -> std::io::Result<std::process::Output>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Self {
+            Self {
+                calls: Arc::new(Mutex::new(Vec::new())),
+                behavior: Arc::new(behavior),
+            }
+        }
+    }
+
+    // Builds an ExitStatus carrying the given exit code
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> std::process::ExitStatus {
+        std::process::ExitStatus::from_raw(code << 8)
+    }
+
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> std::process::ExitStatus {
+        std::process::ExitStatus::from_raw(code as u32)
+    }
+
+    fn mock_success() -> std::process::Output {
+        std::process::Output {
+            status: exit_status(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn is_stream_probe(program: &str, args: &[&str]) -> bool {
+        program == "ffprobe" && args.contains(&"-show_streams")
+    }
+
+    // What ffprobe -show_streams prints for a file with a video and an audio stream
+    fn mock_video_streams() -> std::process::Output {
+        mock_stdout(
+            "[STREAM]\nindex=0\ncodec_type=video\n[/STREAM]\n\
+             [STREAM]\nindex=1\ncodec_type=audio\n[/STREAM]\n",
+        )
+    }
+
+    // What ffprobe -show_streams prints for a silent clip such as a screen recording
+    fn mock_silent_streams() -> std::process::Output {
+        mock_stdout("[STREAM]\nindex=0\ncodec_type=video\n[/STREAM]\n")
+    }
+
+    // Succeeds for every command, reporting a video stream to the input probe
+    fn mock_ok(program: &str, args: &[&str]) -> std::io::Result<std::process::Output> {
+        if is_stream_probe(program, args) {
+            Ok(mock_video_streams())
+        } else {
+            Ok(mock_success())
+        }
+    }
+
+    fn mock_stdout(stdout: &str) -> std::process::Output {
+        std::process::Output {
+            status: exit_status(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn mock_failure(stderr: &str) -> std::process::Output {
+        std::process::Output {
+            status: exit_status(1),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_generate_output_filename() {
+        let reverser = VideoReverser::new();
+        let input = Path::new("test.mp4");
+        let output = reverser.generate_output_filename(input);
+        assert_eq!(output.to_str().unwrap(), "test-rev.mp4");
+    }
+
+    #[test]
+    fn test_extensions_match_ignoring_case() {
+        let dir = tempdir().unwrap();
+        let default = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        let custom = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .allowed_extensions(vec!["mTs".to_string()])
+            .build();
+
+        for (reverser, name, expected) in [
+            (&default, "CLIP.MP4", "CLIP-rev.MP4"),
+            (&default, "clip.Mp4", "clip-rev.Mp4"),
+            (&custom, "cam.MTS", "cam-rev.MTS"),
+            (&custom, "cam.mts", "cam-rev.mts"),
+        ] {
+            let file_path = dir.path().join(name);
+            fs::write(&file_path, "test content").unwrap();
+            let output = reverser.reverse_video(&file_path).unwrap();
+            assert_eq!(output, dir.path().join(expected));
+        }
+        let other = dir.path().join("clip.MP4");
+        fs::write(&other, "test content").unwrap();
+        assert!(matches!(
+            custom.reverse_video(&other),
+            Err(VideoError::InvalidInput(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_file_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        // "café" in Latin-1, which isn't valid UTF-8; the extension's case doesn't matter
+        let file_path = dir.path().join(OsStr::from_bytes(b"caf\xe9.MP4"));
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(
+            dir.path().join(OsStr::from_bytes(b"old\xe9-rev.mp4")),
+            "earlier output",
+        )
+        .unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let output = reverser.reverse_video(&file_path).unwrap();
+        assert_eq!(output.file_name().unwrap().as_bytes(), b"caf\xe9-rev.MP4");
+
+        // Only the new output is picked up again, and recognised as one
+        fs::remove_file(&output).unwrap();
+        let results = reverser.reverse_directory(dir.path());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, file_path);
+    }
+
+    #[test]
+    fn test_generate_output_filename_edge_cases() {
+        let reverser = VideoReverser::new();
+        let cases = [
+            ("video", "video-rev"),
+            (".mp4", ".mp4-rev"),
+            (".clip.mp4", ".clip-rev.mp4"),
+            ("archive.tar.mp4", "archive.tar-rev.mp4"),
+            ("movie.", "movie-rev"),
+            ("dir/sub/test.mp4", "dir/sub/test-rev.mp4"),
+        ];
+        for (input, expected) in cases {
+            let output = reverser.generate_output_filename(Path::new(input));
+            assert_eq!(output, Path::new(expected), "input {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_output_derived_from_resolved_input() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("clips")).unwrap();
+        let real = dir.path().join("clips").join("clip.mp4");
+        fs::write(&real, "test content").unwrap();
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+
+        let dotted = dir
+            .path()
+            .join("clips")
+            .join("..")
+            .join("clips")
+            .join("clip.mp4");
+        assert_eq!(
+            reverser.reverse_video(&dotted).unwrap(),
+            dir.path().join("clips").join("clip-rev.mp4")
+        );
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link.mp4");
+            std::os::unix::fs::symlink(&real, &link).unwrap();
+            assert_eq!(
+                reverser.reverse_video(&link).unwrap(),
+                dir.path().join("clips").join("clip-rev.mp4")
+            );
+            // An explicit output is used as given
+            let explicit = dir.path().join("link-rev.mp4");
+            assert_eq!(
+                reverser.reverse_video_to(&link, &explicit).unwrap(),
+                explicit
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_format_changes_extension() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mkv");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder().output_format("mp4").build();
+        let command = reverser.plan_command(&file_path).unwrap();
+        assert_eq!(
+            command.args.last().unwrap(),
+            dir.path().join("clip-rev.mp4").as_os_str()
+        );
+
+        let unknown = VideoReverser::builder().output_format("flv").build();
+        assert!(matches!(
+            unknown.plan_command(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+        let mismatched = VideoReverser::builder()
+            .output_format("webm")
+            .encoder("libx264")
+            .build();
+        assert!(matches!(
+            mismatched.plan_command(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_supported_output_formats() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"-formats") {
+                return Ok(mock_stdout(
+                    "File formats:\n D. = Demuxing supported\n .E = Muxing supported\n --\n \
+                     D  avi             AVI (Audio Video Interleaved)\n  \
+                     E mov             QuickTime / MOV\n \
+                     DE matroska,webm   Matroska / WebM\n  \
+                     E mp4             MP4 (MPEG-4 Part 14)\n",
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .output_format("avi")
+            .build();
+
+        assert_eq!(
+            reverser.supported_output_formats().unwrap(),
+            ["mp4", "mov", "mkv", "webm"]
+        );
+        let err = reverser.reverse_video(&file_path).unwrap_err();
+        assert!(err.to_string().contains("can't write avi files"));
+        // The second lookup came from the cache
+        let calls = calls.lock().unwrap();
+        let listings = calls
+            .iter()
+            .filter(|(_, args)| args.contains(&"-formats".to_string()));
+        assert_eq!(listings.count(), 1);
+    }
+
+    #[test]
+    fn test_ffmpeg_check_is_cached() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("a.mp4");
+        let second = dir.path().join("b.mp4");
+        fs::write(&first, "test content").unwrap();
+        fs::write(&second, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args == ["-version"] {
+                return Ok(mock_stdout("ffmpeg version N-112345-gabcdef"));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let mut reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .probe_validation(false)
+            .min_ffmpeg_version(4, 0)
+            .build();
+        let version_checks = || {
+            calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(program, args)| program == "ffmpeg" && args == &["-version"])
+                .count()
+        };
+
+        reverser.reverse_video(&first).unwrap();
+        let outcome = reverser.reverse_video_detailed(&second).unwrap();
+        assert_eq!(version_checks(), 1);
+        // The cached check still reports its warning
+        assert!(outcome
+            .warnings
+            .contains(&Warning::UnknownFFmpegVersion { assumed: (4, 0) }));
+
+        reverser.clear_ffmpeg_cache();
+        reverser.reverse_video(&first).unwrap();
+        assert_eq!(version_checks(), 2);
+    }
+
+    #[test]
+    fn test_output_template() {
+        let dir = tempdir().unwrap();
+        let clips = dir.path().join("clips");
+        fs::create_dir(&clips).unwrap();
+        let file_path = clips.join("jump.mkv");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder()
+            .output_template("{parent}-{stem}_{index}.{ext}")
+            .output_format("mp4")
+            .build();
+        assert_eq!(
+            reverser.numbered_output_filename(&file_path, 3),
+            clips.join("clips-jump_3.mp4")
+        );
+
+        // Separators can't move the output out of the input's directory
+        let escaping = VideoReverser::builder()
+            .output_template("../{stem}")
+            .build();
+        assert_eq!(
+            escaping.generate_output_filename(&file_path),
+            clips.join(".._jump")
+        );
+
+        let unknown = VideoReverser::builder()
+            .output_template("{stem}-{when}")
+            .build();
+        let err = unknown.reverse_video(&file_path).unwrap_err();
+        assert!(err.to_string().contains("Unknown placeholder {when}"));
+        assert!(check_template("{stem").is_err());
+        assert!(check_template("{date}{duration}").is_ok());
+    }
+
+    #[test]
+    fn test_templated_outputs_are_not_inputs() {
+        for (template, name, fits) in [
+            ("{stem}_rev_{date}.{ext}", "a_rev_2026-10-15.mp4", true),
+            ("{stem}_rev_{date}.{ext}", "a_rev_today.mp4", false),
+            ("{parent}-{stem}_{index}.{ext}", "x-y-z_12.mov", true),
+            ("{parent}-{stem}_{index}.{ext}", "x-y_z.mov", false),
+            ("{stem} ({duration}s).{ext}", "é (12s).mp4", true),
+            ("{stem} ({duration}s).{ext}", "é (unknowns).mp4", true),
+            ("{stem} ({duration}s).{ext}", "é (12 s).mp4", false),
+            // Fits every name, so nothing is taken for an output
+            ("{stem}.{ext}", "a.mp4", false),
+        ] {
+            assert_eq!(matches_template(template, name), fits, "{template} {name}");
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.mp4"), "test content").unwrap();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .output_template("{stem}_rev_{date}.{ext}")
+            .build();
+
+        for _ in 0..2 {
+            let results = reverser.reverse_directory(dir.path());
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].0, dir.path().join("a.mp4"));
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_utc_date() {
+        let at = |secs| utc_date(std::time::UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(at(0), "1970-01-01");
+        assert_eq!(at(951_782_400), "2000-02-29");
+        assert_eq!(at(1_791_936_000), "2026-10-14");
+    }
+
+    #[test]
+    fn test_custom_suffix_and_prefix() {
+        let input = Path::new("dir/clip.mp4");
+        let suffixed = VideoReverser::builder().suffix("_reversed").build();
+        assert_eq!(
+            suffixed.generate_output_filename(input),
+            Path::new("dir/clip_reversed.mp4")
+        );
+        assert!(suffixed.is_reversed_output(Path::new("clip_reversed.mp4")));
+        assert!(!suffixed.is_reversed_output(Path::new("clip-rev.mp4")));
+
+        let prefixed = VideoReverser::builder().suffix("rev-").prefix(true).build();
+        assert_eq!(
+            prefixed.generate_output_filename(input),
+            Path::new("dir/rev-clip.mp4")
+        );
+        assert!(prefixed.is_reversed_output(Path::new("rev-clip.mp4")));
+        assert!(!prefixed.is_reversed_output(Path::new("clip.mp4")));
+
+        let unicode = VideoReverser::builder().suffix("·逆再生").build();
+        assert_eq!(
+            unicode.generate_output_filename(Path::new("café.mov")),
+            Path::new("café·逆再生.mov")
+        );
+    }
+
+    #[test]
+    fn test_invalid_input_file() {
+        let reverser = VideoReverser::new();
+        let result = reverser.reverse_video("nonexistent.mp4");
+        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_invalid_file_extension() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::new();
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_other_containers_accepted() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mkv");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video(&file_path);
+
+        assert_eq!(result.unwrap(), dir.path().join("test-rev.mkv"));
+    }
+
+    #[test]
+    fn test_custom_allowed_extensions() {
+        let dir = tempdir().unwrap();
+        let mp4_path = dir.path().join("test.mp4");
+        let ts_path = dir.path().join("test.ts");
+        fs::write(&mp4_path, "test content").unwrap();
+        fs::write(&ts_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .allowed_extensions(vec!["ts".to_string()])
+            .build();
+
+        assert!(reverser.reverse_video(&ts_path).is_ok());
+        assert!(matches!(
+            reverser.reverse_video(&mp4_path),
+            Err(VideoError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_ffmpeg_path() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .ffmpeg_path("/opt/ffmpeg/bin/ffmpeg")
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+
+        let calls = calls.lock().unwrap();
+        let ffmpeg_calls: Vec<_> = calls.iter().filter(|(p, _)| p != "ffprobe").collect();
+        assert_eq!(ffmpeg_calls.len(), 2);
+        assert!(ffmpeg_calls
+            .iter()
+            .all(|(program, _)| program == "/opt/ffmpeg/bin/ffmpeg"));
+    }
+
+    #[test]
+    fn test_default_ffmpeg_path_env_fallback() {
+        assert_eq!(default_tool_path(None, "ffmpeg"), PathBuf::from("ffmpeg"));
+        assert_eq!(
+            default_tool_path(Some(OsString::new()), "ffmpeg"),
+            PathBuf::from("ffmpeg")
+        );
+        assert_eq!(
+            default_tool_path(Some("/opt/ffmpeg/bin/ffmpeg".into()), "ffmpeg"),
+            PathBuf::from("/opt/ffmpeg/bin/ffmpeg")
+        );
+    }
+
+    #[test]
+    fn test_missing_ffprobe_is_skipped() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // Only ffmpeg is installed
+        let runner = MockCommandRunner::new(|program, _| {
+            if program == "/opt/ffprobe" {
+                Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .ffprobe_path("/opt/ffprobe")
+            .build();
+
+        assert!(matches!(
+            reverser.check_ffprobe(),
+            Err(VideoError::FFprobeNotFound)
+        ));
+        let outcome = reverser.reverse_video_detailed(&file_path).unwrap();
+        assert_eq!(outcome.path, dir.path().join("test-rev.mp4"));
+        assert_eq!(outcome.duration, None);
+        assert!(calls
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(p, _)| p == "/opt/ffprobe"));
+    }
+
+    #[test]
+    fn test_ffmpeg_not_found_mock() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // Mock runner that fails on -version check
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffmpeg" && args.contains(&"-version") {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "not found",
+                ))
+            } else {
+                Ok(mock_success())
+            }
+        });
+
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(result, Err(VideoError::FFmpegNotFound)));
+    }
+
+    #[test]
+    fn test_clones_share_the_runner() {
+        let dir = tempdir().unwrap();
+        let inputs: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.path().join(format!("clip{i}.mp4"));
+                fs::write(&path, "test content").unwrap();
+                path
+            })
+            .collect();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let configured = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .crf(20)
+            .build();
+        std::thread::scope(|scope| {
+            for input in &inputs {
+                let reverser = configured.clone();
+                scope.spawn(move || reverser.reverse_video(input).unwrap());
+            }
+        });
+
+        let calls = calls.lock().unwrap();
+        let encodes = calls
+            .iter()
+            .filter(|(_, args)| args.contains(&"-crf".to_string()));
+        assert_eq!(encodes.count(), 3);
+        assert_eq!(
+            VideoReverser::default().build_ffmpeg_args("a.mp4", "b.mp4"),
+            VideoReverser::new().build_ffmpeg_args("a.mp4", "b.mp4")
+        );
+    }
+
+    #[test]
+    fn test_ffmpeg_not_found() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // A missing binary is injected instead of clearing PATH, which other tests share
+        let reverser = VideoReverser::builder()
+            .ffmpeg_path(dir.path().join("no-such-ffmpeg"))
+            .probe_validation(false)
+            .build();
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(result, Err(VideoError::FFmpegNotFound)));
+        assert!(!dir.path().join("test-rev.mp4").exists());
+    }
+
+    #[test]
+    fn test_successful_video_reverse_mock() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let expected_output = dir.path().join("test-rev.mp4");
+
+        // Mock runner that succeeds
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), expected_output);
+
+        // Verify calls
+        let calls = calls.lock().unwrap();
+        // 1. check_ffmpeg
+        assert_eq!(calls[0].0, "ffmpeg");
+        assert_eq!(calls[0].1, vec!["-version"]);
+        // 2. validate_input
+        assert_eq!(calls[1].0, "ffprobe");
+        assert!(calls[1].1.contains(&"-show_streams".to_string()));
+        // 3. reverse_video
+        assert_eq!(calls[2].0, "ffmpeg");
+        assert!(calls[2].1.contains(&"-i".to_string()));
+        assert!(calls[2]
+            .1
+            .contains(&file_path.to_str().unwrap().to_string()));
+    }
+
+    #[test]
+    fn test_reverse_modes_select_filters() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        reverser
+            .reverse_video_with_mode(&file_path, ReverseMode::VideoOnly)
+            .unwrap();
+        reverser
+            .reverse_video_with_mode(&file_path, ReverseMode::AudioOnly)
+            .unwrap();
+
+        let calls = calls.lock().unwrap();
+        let video_only = &calls[2].1;
+        assert_eq!(video_only[2..6], ["-vf", "reverse", "-c:a", "copy"]);
+        assert!(!video_only.contains(&"areverse".to_string()));
+        // The second run reuses the ffmpeg check, so it starts with the probe
+        let audio_only = &calls[4].1;
+        assert_eq!(audio_only[2..6], ["-c:v", "copy", "-af", "areverse"]);
+        assert!(!audio_only.contains(&"reverse".to_string()));
+    }
+
+    #[test]
+    fn test_builder_mode_applies_to_reverse_video() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .mode(ReverseMode::VideoOnly)
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[2].1[2..6], ["-vf", "reverse", "-c:a", "copy"]);
+    }
+
+    #[test]
+    fn test_reverse_concat() {
+        let dir = tempdir().unwrap();
+        let inputs: Vec<_> = ["part1.mp4", "part2.mp4", "odd.mp4"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for input in &inputs {
+            fs::write(input, "test content").unwrap();
+        }
+        let output = dir.path().join("whole-rev.mp4");
+
+        let mock = |program: &str, args: &[&str]| {
+            if args.contains(&"-show_format") {
+                let width = if args.last().unwrap().ends_with("odd.mp4") {
+                    1280
+                } else {
+                    1920
+                };
+                return Ok(mock_stdout(&format!(
+                    r#"{{"streams": [
+                        {{"codec_name": "h264", "codec_type": "video", "width": {width}, "height": 1080}},
+                        {{"codec_name": "aac", "codec_type": "audio"}}
+                    ], "format": {{"duration": "5.0"}}}}"#
+                )));
+            }
+            // The demuxer list is gone once the reversal returns, so read it now
+            if let Some(list) = args.iter().find(|arg| arg.contains(".concat.tmp-")) {
+                let list = fs::read_to_string(list).unwrap();
+                assert_eq!(list.lines().count(), 2);
+                assert!(list.lines().all(|line| line.starts_with("file '")));
+            }
+            mock_ok(program, args)
+        };
+        let runner = MockCommandRunner::new(mock);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let result = reverser.reverse_concat(&inputs[..2], &output).unwrap();
+        assert_eq!(result, output);
+        assert!(output.exists());
+        let ffmpeg = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(program, args)| program == "ffmpeg" && args.contains(&"concat".to_string()))
+            .cloned()
+            .unwrap();
+        assert!(ffmpeg
+            .1
+            .windows(4)
+            .any(|w| w == ["-f", "concat", "-safe", "0"]));
+        assert!(ffmpeg.1.windows(2).any(|w| w == ["-vf", "reverse"]));
+
+        // Inputs the concat demuxer can't join are rejected before ffmpeg runs
+        fs::remove_file(&output).unwrap();
+        let result = reverser.reverse_concat(&inputs, &output);
+        assert!(
+            matches!(result, Err(VideoError::InvalidInput(message)) if message.contains("dimensions"))
+        );
+        assert!(!output.exists());
+        assert!(matches!(
+            reverser.reverse_concat(&[], &output),
+            Err(VideoError::InvalidInput(_))
+        ));
+
+        // Like any reversal, the joined clip is decoded before it becomes the output
+        let runner = MockCommandRunner::new(mock);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .verify_output(true)
+            .build();
+        reverser.reverse_concat(&inputs[..2], &output).unwrap();
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.last().unwrap().1[..3], ["-v", "error", "-i"]);
+        assert!(output.exists());
+    }
+
+    #[test]
+    fn test_probe_video_info() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"-show_format") {
+                return Ok(mock_stdout(
+                    r#"{
+                        "streams": [
+                            {"index": 0, "codec_name": "h264", "codec_type": "video",
+                             "width": 1920, "height": 1080, "r_frame_rate": "30000/1001",
+                             "avg_frame_rate": "30000/1001", "nb_frames": "300"},
+                            {"index": 1, "codec_name": "aac", "codec_type": "audio",
+                             "r_frame_rate": "0/0", "avg_frame_rate": "0/0"}
+                        ],
+                        "format": {"duration": "10.010000", "bit_rate": "5000000"}
+                    }"#,
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let info = reverser.probe_video_info(&file_path).unwrap();
+        assert_eq!(
+            info,
+            VideoInfo {
+                duration: Some(Duration::from_millis(10010)),
+                width: Some(1920),
+                height: Some(1080),
+                frame_rate: Some((30000, 1001)),
+                frame_count: Some(300),
+                video_codec: Some("h264".to_string()),
+                audio_codec: Some("aac".to_string()),
+                bit_rate: Some(5_000_000),
+            }
+        );
+        assert!((info.fps().unwrap() - 29.97).abs() < 0.01);
+
+        // Without nb_frames the count comes from the duration
+        let counted = VideoInfo::parse(
+            r#"{"streams": [{"codec_type": "video", "avg_frame_rate": "0/0", "r_frame_rate": "25/1"}],
+                "format": {"duration": "2.0"}}"#,
+        )
+        .unwrap();
+        assert_eq!(counted.frame_count, Some(50));
+    }
+
+    #[test]
+    fn test_estimate_output_size() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let probed = || {
+            MockCommandRunner::new(|program, args| {
+                if args.contains(&"format=duration,bit_rate") {
+                    return Ok(mock_stdout("duration=10.000000\nbit_rate=800000\n"));
+                }
+                mock_ok(program, args)
+            })
+        };
+
+        let reverser = VideoReverser::new_with_runner(Box::new(probed()));
+        assert_eq!(
+            reverser.estimate_output_size(&file_path).unwrap(),
+            1_000_000
+        );
+        let smaller = VideoReverser::builder()
+            .runner(Box::new(probed()))
+            .crf(29)
+            .speed(2.0)
+            .build();
+        assert_eq!(smaller.estimate_output_size(&file_path).unwrap(), 250_000);
+
+        // Without a bitrate the input size stands in
+        let unprobed = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        assert_eq!(unprobed.estimate_output_size(&file_path).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_progress_callback_reports_fractions() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Ok(mock_stdout("10.000000\n"))
+            } else if args.contains(&"-version") {
+                Ok(mock_success())
+            } else {
+                Ok(mock_stdout(
+                    "out_time_us=5000000\nprogress=continue\nout_time_us=8000000\nprogress=end\n",
+                ))
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        assert_eq!(
+            reverser.input_duration(&file_path),
+            Some(Duration::from_secs(10))
+        );
+        let mut reports = Vec::new();
+        let result = reverser.reverse_video_with_progress(&file_path, |p| reports.push(p));
+
+        assert!(result.is_ok());
+        // The 8s update arrives within the throttle window and is dropped
+        assert_eq!(reports, vec![0.5, 1.0]);
+        let calls = calls.lock().unwrap();
+        let ffmpeg_args = &calls.last().unwrap().1;
+        assert_eq!(ffmpeg_args[..3], ["-progress", "pipe:1", "-nostats"]);
+    }
+
+    #[test]
+    fn test_progress_without_duration_reports_completion() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Ok(mock_failure("ffprobe failed"))
+            } else if args.contains(&"-version") {
+                Ok(mock_success())
+            } else {
+                Ok(mock_stdout("out_time_us=5000000\nprogress=end\n"))
+            }
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        assert_eq!(reverser.input_duration(&file_path), None);
+        let mut reports = Vec::new();
+        reverser
+            .reverse_video_to_with_progress(&file_path, dir.path().join("out.mp4"), |p| {
+                reports.push(p)
+            })
+            .unwrap();
+
+        assert_eq!(reports, vec![1.0]);
+    }
+
+    #[test]
+    fn test_reverse_video_events_reports_progress_then_outcome() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if args.contains(&"stream=width,height:format=duration") {
+                Ok(mock_stdout("duration=10.000000\n"))
+            } else if program == "ffprobe" {
+                Ok(mock_stdout("10.000000\n"))
+            } else if args.contains(&"-version") {
+                Ok(mock_success())
+            } else {
+                Ok(mock_stdout(
+                    "frame=120\nfps=25.0\nout_time_us=5000000\nspeed=2.5x\nprogress=continue\n\
+                     fps=24.0\nout_time_us=10000000\nspeed=N/A\nprogress=end\n",
+                ))
+            }
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let events: Vec<_> = reverser.reverse_video_events(&file_path).collect();
+
+        assert_eq!(events.len(), 4, "{events:?}");
+        assert!(matches!(events[0], ReverseEvent::Started));
+        let ReverseEvent::Progress(first) = &events[1] else {
+            panic!("expected progress, got {:?}", events[1]);
+        };
+        assert_eq!(
+            *first,
+            ProgressUpdate {
+                time: Duration::from_secs(5),
+                fps: Some(25.0),
+                speed: Some(2.5),
+                fraction: Some(0.5),
+            }
+        );
+        // The end block is reported despite the throttle
+        let ReverseEvent::Progress(last) = &events[2] else {
+            panic!("expected progress, got {:?}", events[2]);
+        };
+        assert_eq!(last.time, Duration::from_secs(10));
+        assert_eq!(
+            (last.fps, last.speed, last.fraction),
+            (Some(24.0), None, Some(1.0))
+        );
+        let ReverseEvent::Finished(outcome) = &events[3] else {
+            panic!("expected finished, got {:?}", events[3]);
+        };
+        assert_eq!(outcome.path, dir.path().join("test-rev.mp4"));
+        assert_eq!(outcome.duration, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_reverse_video_events_ends_with_failure() {
+        let dir = tempdir().unwrap();
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(|_, _| {
+            Ok(mock_success())
+        })));
+
+        let mut events = reverser.reverse_video_events(dir.path().join("missing.mp4"));
+
+        assert!(matches!(events.next(), Some(ReverseEvent::Started)));
+        assert!(matches!(events.next(), Some(ReverseEvent::Failed(_))));
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn test_dry_run_skips_ffmpeg() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let output_path = dir.path().join("out").join("test.mp4");
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .dry_run(true)
+            .build();
+
+        let result = reverser.reverse_video_to(&file_path, &output_path);
+
+        assert_eq!(result.unwrap(), output_path);
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(!output_path.parent().unwrap().exists());
+    }
+
+    #[test]
+    fn test_plan_command() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("my clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder().ffmpeg_path("/opt/ffmpeg").build();
+        let command = reverser.plan_command(&file_path).unwrap();
+
+        assert_eq!(command.program, "/opt/ffmpeg");
+        let output_path = dir.path().join("my clip-rev.mp4");
+        let expected: Vec<OsString> = vec![
+            "-i".into(),
+            file_path.clone().into(),
+            "-vf".into(),
+            "reverse".into(),
+            "-af".into(),
+            "areverse".into(),
+            "-sn".into(),
+            "-dn".into(),
+            "-map_metadata".into(),
+            "0".into(),
+            "-y".into(),
+            output_path.clone().into(),
+        ];
+        assert_eq!(command.args, expected);
+        assert_eq!(
+            command.to_string(),
+            format!(
+                "/opt/ffmpeg -i '{}' -vf reverse -af areverse -sn -dn -map_metadata 0 -y '{}'",
+                file_path.display(),
+                output_path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_special_characters_in_paths() {
+        let dir = tempdir().unwrap();
+        let mut names = vec![
+            ("my clip.mp4", "my clip-rev.mp4"),
+            ("it's \"quoted\".mp4", "it's \"quoted\"-rev.mp4"),
+            ("café 🎬 $HOME;ls.mp4", "café 🎬 $HOME;ls-rev.mp4"),
+        ];
+        // Windows doesn't allow quotes or control characters in file names
+        if cfg!(windows) {
+            names.truncate(1);
+        } else {
+            names.push(("line\nbreak.mp4", "line\nbreak-rev.mp4"));
+        }
+
+        for (name, expected) in names {
+            let file_path = dir.path().join(name);
+            fs::write(&file_path, "test content").unwrap();
+            let runner = MockCommandRunner::new(mock_ok);
+            let calls = runner.calls.clone();
+            let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+            let output = reverser.reverse_video(&file_path).unwrap();
+            assert_eq!(output, dir.path().join(expected));
+            assert!(output.exists());
+            // The path is one argument, exactly as given
+            let input = file_path.to_str().unwrap();
+            assert!(calls.lock().unwrap().iter().any(|(program, args)| {
+                program == "ffmpeg" && args.windows(2).any(|w| w == ["-i", input])
+            }));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_runner_passes_arguments_verbatim() {
+        let arg = "it's \"quoted\" `$HOME`; café\n🎬";
+        let output = RealCommandRunner
+            .run(OsStr::new("printf"), &["%s".into(), arg.into()])
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), arg);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("reverse"), "reverse");
+        assert_eq!(shell_quote("/tmp/a-b_c.mp4"), "/tmp/a-b_c.mp4");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("my clip.mp4"), "'my clip.mp4'");
+        assert_eq!(shell_quote("it's.mp4"), r"'it'\''s.mp4'");
+    }
+
+    #[test]
+    fn test_match_source_encoding() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Ok(mock_stdout(
+                    "codec_name=h264|codec_type=video|bit_rate=2000000\n\
+                     codec_name=aac|codec_type=audio|bit_rate=128000\n",
+                ))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .match_source_encoding(true)
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+        reverser
+            .reverse_video_with_mode(&file_path, ReverseMode::VideoOnly)
+            .unwrap();
+
+        let calls = calls.lock().unwrap();
+        let ffmpeg_calls: Vec<_> = calls
+            .iter()
+            .filter(|(program, args)| program == "ffmpeg" && args[0] == "-i")
+            .collect();
+        assert_eq!(
+            ffmpeg_calls[0].1[6..14],
+            ["-c:v", "h264", "-b:v", "2000000", "-c:a", "aac", "-b:a", "128000"]
+        );
+        // Audio is copied in video-only mode, so only the video settings are mirrored
+        assert_eq!(
+            ffmpeg_calls[1].1[6..10],
+            ["-c:v", "h264", "-b:v", "2000000"]
+        );
+        assert!(!ffmpeg_calls[1].1.contains(&"-b:a".to_string()));
+    }
+
+    #[test]
+    fn test_match_source_encoding_without_ffprobe() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "not found",
+                ))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .match_source_encoding(true)
+            .build();
+
+        assert!(reverser.reverse_video(&file_path).is_ok());
+        let calls = calls.lock().unwrap();
+        assert!(!calls.last().unwrap().1.contains(&"-c:v".to_string()));
+    }
+
+    #[test]
+    fn test_source_encoding_parse_missing_bitrate() {
+        let encoding = SourceEncoding::parse(
+            "codec_name=vp9|codec_type=video|bit_rate=N/A\n\
+             codec_name=opus|codec_type=audio|bit_rate=96000\n\
+             codec_name=aac|codec_type=audio|bit_rate=128000\n",
+        );
+        assert_eq!(
+            encoding,
+            SourceEncoding {
+                video_codec: Some("vp9".to_string()),
+                video_bitrate: None,
+                audio_codec: Some("opus".to_string()),
+                audio_bitrate: Some(96000),
+            }
+        );
+    }
+
+    #[test]
+    fn test_reverse_segment() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Ok(mock_stdout("90.5\n"))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let result = reverser.reverse_segment(
+            &file_path,
+            Duration::from_secs(30),
+            Duration::from_millis(60_500),
+        );
+
+        assert_eq!(result.unwrap(), dir.path().join("clip-rev-30s-60.5s.mp4"));
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        assert_eq!(
+            args[..6],
+            ["-i", args[1].as_str(), "-ss", "30", "-to", "60.5"]
+        );
+        assert_eq!(args[1], file_path.to_str().unwrap());
+    }
+
+    #[test]
+    fn test_reverse_to_bytes_cleans_up_scratch_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("to-bytes-scratch-check.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let bytes = reverser.reverse_to_bytes(&file_path).unwrap();
+
+        assert_eq!(bytes, b"reversed");
+        let leftovers = fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .contains("to-bytes-scratch-check")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn test_reverse_reader_spools_to_hinted_extension() {
+        let scratch = tempdir().unwrap();
+        let runner = MockCommandRunner::new(|program, args| {
+            // The spooled input must be complete by the time ffmpeg sees it
+            if let Some(input) = args.iter().find(|a| a.contains(".input.tmp-")) {
+                assert!(input.ends_with(".mov"), "{input}");
+                assert_eq!(fs::read(input).unwrap(), b"uploaded");
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .temp_dir(scratch.path())
+            .build();
+
+        let bytes = reverser.reverse_reader(&b"uploaded"[..], ".mov").unwrap();
+
+        assert_eq!(bytes, b"reversed");
+        assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+        assert!(matches!(
+            reverser.reverse_reader(&b"uploaded"[..], "../mp4"),
+            Err(VideoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            reverser.reverse_reader(&b"uploaded"[..], "txt"),
+            Err(VideoError::InvalidInput(_))
+        ));
+        assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_stream_selection() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("dual.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let dual_angle = || {
+            MockCommandRunner::new(|program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(
+                        "[STREAM]\nindex=0\ncodec_name=h264\ncodec_type=video\n[/STREAM]\n\
+                         [STREAM]\nindex=1\ncodec_name=h264\ncodec_type=video\n[/STREAM]\n\
+                         [STREAM]\nindex=2\ncodec_name=aac\ncodec_type=audio\n[/STREAM]\n",
+                    ));
+                }
+                mock_ok(program, args)
+            })
+        };
+
+        let streams = VideoReverser::new_with_runner(Box::new(dual_angle()))
+            .list_streams(&file_path)
+            .unwrap();
+        assert_eq!(streams.len(), 3);
+        assert_eq!(
+            streams[1],
+            StreamInfo {
+                index: 1,
+                codec_type: "video".to_string(),
+                codec_name: Some("h264".to_string()),
+                rotation: 0,
+                variable_frame_rate: false,
+                pixel_format: None,
+                width: None,
+                height: None,
+            }
+        );
+
+        let runner = dual_angle();
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .video_stream(1)
+            .build();
+        reverser.reverse_video(&file_path).unwrap();
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        assert_eq!(
+            args[2..8],
+            ["-map", "0:1", "-map", "0:a:0?", "-vf", "reverse"]
+        );
+        drop(calls);
+
+        let wrong_type = VideoReverser::builder()
+            .runner(Box::new(dual_angle()))
+            .audio_stream(0)
+            .build();
+        let err = wrong_type.reverse_video(&file_path).unwrap_err();
+        assert!(err.to_string().contains("is video, not audio"));
+    }
+
+    #[test]
+    fn test_subtitle_streams_dropped_or_copied() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("subbed.mkv");
+        fs::write(&file_path, "test content").unwrap();
+
+        let subtitled = || {
+            MockCommandRunner::new(|program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(
+                        "[STREAM]\nindex=0\ncodec_type=video\n[/STREAM]\n\
+                         [STREAM]\nindex=1\ncodec_type=audio\n[/STREAM]\n\
+                         [STREAM]\nindex=2\ncodec_name=subrip\ncodec_type=subtitle\n[/STREAM]\n",
+                    ));
+                }
+                mock_ok(program, args)
+            })
+        };
+        let ffmpeg_args = |reverser: VideoReverser, calls: CallLog| {
+            reverser.reverse_video(&file_path).unwrap();
+            calls.lock().unwrap().last().unwrap().1.clone()
+        };
+
+        let runner = subtitled();
+        let calls = runner.calls.clone();
+        let args = ffmpeg_args(VideoReverser::new_with_runner(Box::new(runner)), calls);
+        assert_eq!(args[6..8], ["-sn", "-dn"]);
+        // ffmpeg's default selection keeps one video and one audio stream
+        assert!(!args.contains(&"-map".to_string()));
+
+        let runner = subtitled();
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .drop_non_av_streams(false)
+            .video_stream(0)
+            .build();
+        let args = ffmpeg_args(reverser, calls);
+        assert_eq!(
+            args[2..8],
+            ["-map", "0:0", "-map", "0:a:0?", "-map", "0:s:0?"]
+        );
+        assert!(!args.contains(&"-sn".to_string()));
+        assert!(args.windows(2).any(|pair| pair == ["-c:s", "copy"]));
+    }
+
+    #[test]
+    fn test_reverse_to_gif() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let output = reverser.reverse_to_gif(&file_path, 12, Some(480)).unwrap();
+
+        assert_eq!(output, dir.path().join("clip-rev.gif"));
+        let calls = calls.lock().unwrap();
+        let args = &calls[2].1;
+        assert_eq!(
+            args[2..5],
+            [
+                "-vf",
+                "reverse,fps=12,scale=480:-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse",
+                "-an"
+            ]
+        );
+        assert!(args.last().unwrap().ends_with(".gif"));
+    }
+
+    #[test]
+    fn test_reverse_side_by_side() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .crf(20)
+            .build();
+
+        let output = reverser.reverse_side_by_side(&file_path).unwrap();
+
+        assert_eq!(output, dir.path().join("clip-compare.mp4"));
+        let calls = calls.lock().unwrap();
+        let args = &calls[2].1;
+        assert_eq!(args[2], "-filter_complex");
+        let filter = &args[3];
+        assert!(filter.starts_with("[0:v]split[fwd][rev];"));
+        assert!(filter.contains("[rev]reverse,drawtext=text=Reversed"));
+        assert!(filter.ends_with("[left][right]hstack"));
+        assert_eq!(args[4..7], ["-an", "-crf", "20"]);
+    }
+
+    #[test]
+    fn test_extract_thumbnail() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip-rev.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"format=duration") {
+                Ok(mock_stdout("10.000000\n"))
+            } else {
+                mock_ok(program, args)
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let output = reverser
+            .extract_thumbnail(&file_path, Duration::from_millis(1500))
+            .unwrap();
+
+        assert_eq!(output, dir.path().join("clip-rev.jpg"));
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        assert_eq!(
+            args[..6],
+            [
+                "-ss",
+                "1.5",
+                "-i",
+                file_path.to_str().unwrap(),
+                "-frames:v",
+                "1"
+            ]
+        );
+        assert!(args.last().unwrap().ends_with(".jpg"));
+        drop(calls);
+
+        let result = reverser.extract_thumbnail(&file_path, Duration::from_secs(10));
+        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_reverse_to_image_sequence() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let frames = dir.path().join("frames");
+
+        // Writes three frames, like ffmpeg would for a three-frame clip
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"image2") {
+                let pattern = Path::new(args.last().unwrap());
+                for number in 1..=3 {
+                    let name = format!("frame_{number:04}.png");
+                    fs::write(pattern.with_file_name(name), "png").unwrap();
+                }
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        // Frames of a longer earlier run must not be counted
+        fs::create_dir(&frames).unwrap();
+        for number in 1..=5 {
+            fs::write(frames.join(format!("frame_{number:04}.png")), "stale").unwrap();
+        }
+
+        let count = reverser
+            .reverse_to_image_sequence(&file_path, &frames, "frame_%04d", ImageFormat::Png)
+            .unwrap();
+
+        assert_eq!(count, 3);
+        assert!(!frames.join("frame_0004.png").exists());
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        let vf = args.iter().position(|a| a == "-vf").unwrap();
+        assert_eq!(args[vf + 1], "reverse");
+        assert!(args.windows(2).any(|w| w == ["-f", "image2"]));
+        assert_eq!(
+            Path::new(args.last().unwrap()),
+            frames.join("frame_%04d.png")
+        );
+        drop(calls);
+
+        for pattern in [
+            "frame",
+            "frame_%d",
+            "frame_%4d",
+            "a/frame_%04d",
+            "%04d_%04d",
+        ] {
+            assert!(
+                matches!(
+                    reverser.reverse_to_image_sequence(
+                        &file_path,
+                        &frames,
+                        pattern,
+                        ImageFormat::Png
+                    ),
+                    Err(VideoError::InvalidInput(_))
+                ),
+                "{pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_image_sequence_is_retried_and_verified() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let frames = dir.path().join("frames");
+
+        // The first conversion fails for lack of resources, the second writes two frames
+        let failures = Arc::new(AtomicUsize::new(1));
+        let runner = MockCommandRunner::new(move |program, args| {
+            if args.contains(&"image2") {
+                if failures.fetch_sub(1, Ordering::Relaxed) > 0 {
+                    return Ok(mock_failure("Cannot allocate memory"));
+                }
+                let pattern = Path::new(args.last().unwrap());
+                for number in 1..=2 {
+                    fs::write(pattern.with_file_name(format!("f{number:02}.jpg")), "jpg").unwrap();
+                }
+                return Ok(mock_success());
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .retries(1)
+            .retry_delay(Duration::ZERO)
+            .verify_output(true)
+            .build();
+
+        let count = reverser
+            .reverse_to_image_sequence(&file_path, &frames, "f%02d", ImageFormat::Jpeg)
+            .unwrap();
+
+        assert_eq!(count, 2);
+        let calls = calls.lock().unwrap();
+        let conversions = calls
+            .iter()
+            .filter(|(_, args)| args.contains(&"image2".to_string()))
+            .count();
+        assert_eq!(conversions, 2);
+        // The frames are decoded where they were written
+        let pattern = frames.join("f%02d.jpg");
+        assert_eq!(
+            calls.last().unwrap().1[..4],
+            ["-v", "error", "-i", pattern.to_str().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_reverse_to_image_sequence_respects_overwrite_policy() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(dir.path().join("f0001.jpg"), "jpeg").unwrap();
+        fs::write(dir.path().join("f0002.jpg"), "jpeg").unwrap();
+
+        let reverser = |policy| {
+            VideoReverser::builder()
+                .runner(Box::new(MockCommandRunner::new(mock_ok)))
+                .overwrite(policy)
+                .build()
+        };
+        let run = |policy| {
+            reverser(policy).reverse_to_image_sequence(
+                &file_path,
+                dir.path(),
+                "f%04d.jpg",
+                ImageFormat::Jpeg,
+            )
+        };
+
+        assert_eq!(run(OverwritePolicy::Skip).unwrap(), 2);
+        assert!(matches!(
+            run(OverwritePolicy::Never),
+            Err(VideoError::OutputExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_reverse_per_scene() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"format=duration") {
+                return Ok(mock_stdout("10.000000\n"));
+            }
+            if args.iter().any(|a| a.starts_with("select=")) {
+                return Ok(std::process::Output {
+                    stderr: b"[Parsed_showinfo_1 @ 0x55d0] n:   0 pts:  51200 pts_time:4 duration:512\n\
+                              [Parsed_showinfo_1 @ 0x55d0] n:   1 pts:  89600 pts_time:7.5 duration:512\n"
+                        .to_vec(),
+                    ..mock_success()
+                });
+            }
+            // Every ffmpeg run leaves a file behind for the next step
+            if program == "ffmpeg" && !args.contains(&"-version") {
+                fs::write(args.last().unwrap(), "piece").unwrap();
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = recorded.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .metrics(move |metrics: &ReverseMetrics| {
+                let paths = (metrics.input.to_path_buf(), metrics.output.to_path_buf());
+                sink.lock().unwrap().push(paths)
+            })
+            .build();
+
+        let (output, cuts) = reverser.reverse_per_scene(&file_path, 0.3).unwrap();
+
+        assert_eq!(output, dir.path().join("clip-scene-rev.mp4"));
+        assert_eq!(cuts, [Duration::from_secs(4), Duration::from_millis(7500)]);
+        let calls = calls.lock().unwrap();
+        let ranges: Vec<_> = calls
+            .iter()
+            .filter_map(|(_, args)| {
+                let at = args.iter().position(|a| a == "-ss")?;
+                Some((args[at + 1].clone(), args[at + 3].clone()))
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            [("0", "4"), ("4", "7.5"), ("7.5", "10")].map(|(a, b)| (a.into(), b.into()))
+        );
+        let concat = &calls.last().unwrap().1;
+        assert_eq!(concat[..4], ["-f", "concat", "-safe", "0"]);
+        // Only the result is left in the directory
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 2);
+        // The pieces are part of the one reversal reported
+        assert_eq!(*recorded.lock().unwrap(), [(file_path, output)]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_per_scene_rejects_non_utf8_temp_dir() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        // The concat list can't name pieces in here
+        let temp_dir = dir.path().join(OsStr::from_bytes(b"tmp\xe9"));
+        fs::create_dir(&temp_dir).unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"format=duration") {
+                return Ok(mock_stdout("10.000000\n"));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .temp_dir(&temp_dir)
+            .build();
+
+        let result = reverser.reverse_per_scene(&file_path, 0.3);
+
+        assert!(
+            matches!(&result, Err(VideoError::InvalidInput(message)) if message.contains("UTF-8")),
+            "{result:?}"
+        );
+        // Rejected before any piece is reversed
+        assert!(!calls
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(_, args)| args.contains(&"-ss".to_string())));
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_scene_runs_can_time_out() {
+        // Streams every run, timing out the one with an argument starting with `marker`
+        struct TimesOut(MockCommandRunner, &'static str);
+
+        impl CommandRunner for TimesOut {
+            fn run(
+                &self,
+                program: &OsStr,
+                args: &[OsString],
+            ) -> std::io::Result<std::process::Output> {
+                self.0.run(program, args)
+            }
+
+            fn run_streaming(
+                &self,
+                program: &OsStr,
+                args: &[OsString],
+                _on_line: &mut dyn FnMut(&str),
+                control: &RunControl,
+            ) -> std::io::Result<std::process::Output> {
+                assert_eq!(control.timeout, Some(Duration::from_secs(5)));
+                if args.iter().any(|a| a.to_string_lossy().starts_with(self.1)) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "command timed out",
+                    ));
+                }
+                self.0.run(program, args)
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // Detecting the scenes, then joining the reversed ones
+        for marker in ["select=", "concat"] {
+            let runner = MockCommandRunner::new(|program, args| {
+                if args.contains(&"format=duration") {
+                    return Ok(mock_stdout("10.000000\n"));
+                }
+                mock_ok(program, args)
+            });
+            let reverser = VideoReverser::builder()
+                .runner(Box::new(TimesOut(runner, marker)))
+                .timeout(Duration::from_secs(5))
+                .build();
+
+            let result = reverser.reverse_per_scene(&file_path, 0.3);
+
+            assert!(
+                matches!(result, Err(VideoError::Timeout(t)) if t == Duration::from_secs(5)),
+                "{marker}: {result:?}"
+            );
+            assert!(!dir.path().join("clip-scene-rev.mp4").exists());
+        }
+    }
+
+    #[test]
+    fn test_boomerang_filter() {
+        assert_eq!(
+            Boomerang { loops: 1 }.filter(true, None),
+            "[0:v]split=2[v0][v1];[v1]reverse[vr1];\
+             [0:a]asplit=2[a0][a1];[a1]areverse[ar1];\
+             [v0][a0][vr1][ar1]concat=n=2:v=1:a=1[v][a]"
+        );
+        assert_eq!(
+            Boomerang { loops: 2 }.filter(false, None),
+            "[0:v]split=4[v0][v1][v2][v3];[v1]reverse[vr1];[v3]reverse[vr3];\
+             [v0][vr1][v2][vr3]concat=n=4:v=1:a=0[v]"
+        );
+    }
+
+    #[test]
+    fn test_boomerang() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                Ok(mock_silent_streams())
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        assert!(matches!(
+            reverser.boomerang(&file_path, 0),
+            Err(VideoError::InvalidInput(_))
+        ));
+        let output = reverser.boomerang(&file_path, 1).unwrap();
+
+        assert_eq!(output, dir.path().join("clip-boomerang.mp4"));
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        let filter = args
+            .iter()
+            .position(|arg| arg == "-filter_complex")
+            .unwrap();
+        // The clip is silent, so only the video is looped
+        assert!(args[filter + 1].ends_with("concat=n=2:v=1:a=0[v]"));
+        assert_eq!(args[filter + 2..filter + 5], ["-map", "[v]", "-an"]);
+    }
+
+    #[test]
+    fn test_reverse_to_gif_rejects_zero_fps_and_width() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        assert!(matches!(
+            reverser.reverse_to_gif(&file_path, 0, None),
+            Err(VideoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            reverser.reverse_to_gif(&file_path, 10, Some(0)),
+            Err(VideoError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_fast_seek_places_range_before_input() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .accurate_seek(false)
+            .build();
+
+        reverser
+            .reverse_segment(&file_path, Duration::from_secs(1), Duration::from_secs(2))
+            .unwrap();
+
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        assert_eq!(args[..5], ["-ss", "1", "-to", "2", "-i"]);
+    }
+
+    #[test]
+    fn test_reverse_segment_invalid_ranges() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("clip.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if program == "ffprobe" {
+                Ok(mock_stdout("45.0\n"))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let backwards =
+            reverser.reverse_segment(&file_path, Duration::from_secs(20), Duration::from_secs(10));
+        assert!(matches!(backwards, Err(VideoError::InvalidInput(_))));
+
+        let past_end =
+            reverser.reverse_segment(&file_path, Duration::from_secs(30), Duration::from_secs(60));
+        assert!(matches!(past_end, Err(VideoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_reverse_directory() {
+        let dir = tempdir().unwrap();
+        for name in ["a.mp4", "b.mkv", "a-rev.mp4", "notes.txt"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("c.mp4"), "test content").unwrap();
+
+        // Fail the conversion of b.mkv only
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if args.iter().any(|a| a.ends_with("b.mkv")) && !args.contains(&"-version") {
+                Ok(mock_failure("Conversion failed"))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let results = reverser.reverse_directory(dir.path());
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, dir.path().join("a.mp4"));
+        assert_eq!(
+            results[0].1.as_ref().unwrap(),
+            &dir.path().join("a-rev.mp4")
+        );
+        assert_eq!(results[1].0, dir.path().join("b.mkv"));
+        assert!(matches!(
+            results[1].1,
+            Err(VideoError::ProcessingError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reverse_directory_recursive() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.mp4"), "test content").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("c.mp4"), "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .recursive(true)
+            .build();
+
+        let mut inputs: Vec<_> = reverser
+            .reverse_directory(dir.path())
+            .into_iter()
+            .map(|(input, result)| {
+                assert!(result.is_ok());
+                input
+            })
+            .collect();
+        inputs.sort();
+
+        assert_eq!(
+            inputs,
+            vec![
+                dir.path().join("a.mp4"),
+                dir.path().join("sub").join("c.mp4")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_dir_mirrors_subdirectories() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("clips");
+        fs::create_dir_all(source.join("sub").join("deeper")).unwrap();
+        fs::write(source.join("a.mp4"), "test content").unwrap();
+        fs::write(
+            source.join("sub").join("deeper").join("c.mp4"),
+            "test content",
+        )
+        .unwrap();
+        let out = dir.path().join("out");
+
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .recursive(true)
+            .output_dir(&out)
+            .build();
+        let outputs: Vec<_> = reverser
+            .reverse_directory(&source)
+            .into_iter()
+            .map(|(_, result)| result.unwrap())
+            .collect();
+
+        assert_eq!(
+            outputs,
+            vec![
+                out.join("a-rev.mp4"),
+                out.join("sub").join("deeper").join("c-rev.mp4")
+            ]
+        );
+        assert!(outputs.iter().all(|output| output.exists()));
+        assert!(!source.join("a-rev.mp4").exists());
+
+        // An output directory inside the source tree isn't scanned for inputs
+        let nested = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .recursive(true)
+            .output_template("{stem}.{ext}")
+            .output_dir(source.join("reversed"))
+            .build();
+        assert_eq!(nested.reverse_directory(&source).len(), 2);
+        assert_eq!(nested.reverse_directory(&source).len(), 2);
+        assert_eq!(
+            nested.generate_output_filename(source.join("a.mp4")),
+            source.join("reversed").join("a.mp4")
+        );
+    }
+
+    #[test]
+    fn test_directory_runs_stop_when_cancelled() {
+        let dir = tempdir().unwrap();
+        for name in ["a.mp4", "b.mp4", "c.mp4"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+        let (a, b) = (dir.path().join("a.mp4"), dir.path().join("b.mp4"));
+
+        // Ctrl-C arrives while the first video is reversed, which still finishes
+        let run = |batch: &dyn Fn(&VideoReverser) -> Vec<(PathBuf, bool)>| {
+            let cancel = Arc::new(AtomicBool::new(false));
+            let flag = cancel.clone();
+            let runner = MockCommandRunner::new(move |program, args| {
+                if program == "ffmpeg" && args.contains(&"-i") {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                mock_ok(program, args)
+            });
+            let calls = runner.calls.clone();
+            let reverser = VideoReverser::builder()
+                .runner(Box::new(runner))
+                .cancel_token(cancel)
+                .build();
+            let results = batch(&reverser);
+            // Nothing is run for the later inputs
+            let later = calls.lock().unwrap().iter().any(|(_, args)| {
+                args.iter()
+                    .any(|a| a.ends_with("b.mp4") || a.ends_with("c.mp4"))
+            });
+            assert!(!later);
+            results
+        };
+        // Which inputs have a result, and whether it is a cancellation
+        fn summary<T>(results: Vec<(PathBuf, Result<T, VideoError>)>) -> Vec<(PathBuf, bool)> {
+            let cancelled = |result: Result<T, _>| matches!(result, Err(VideoError::Cancelled));
+            results
+                .into_iter()
+                .map(|(input, result)| (input, cancelled(result)))
+                .collect()
+        }
+        let expected = vec![(a, false), (b, true)];
+
+        let results = run(&|reverser| summary(reverser.reverse_directory(dir.path())));
+        assert_eq!(results, expected);
+        let results = run(&|reverser| {
+            let mut cache = InputCache::default();
+            summary(reverser.reverse_directory_incremental(dir.path(), &mut cache))
+        });
+        assert_eq!(results, expected);
+        let results = run(&|reverser| summary(reverser.reverse_directory_parallel(dir.path(), 1)));
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_reverse_directory_missing_dir() {
+        let reverser = VideoReverser::new();
+        let results = reverser.reverse_directory("does-not-exist");
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].1, Err(VideoError::IoError(_))));
+    }
+
+    #[test]
+    fn test_reverse_directory_incremental_skips_unchanged() {
+        let dir = tempdir().unwrap();
+        for name in ["a.mp4", "b.mp4", "c.mp4"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        let mut cache = InputCache::default();
+
+        let first = reverser.reverse_directory_incremental(dir.path(), &mut cache);
+        assert!(first
+            .iter()
+            .all(|(_, result)| matches!(result, Ok(BatchStatus::Reversed(_)))));
+        assert_eq!(cache.len(), 3);
+
+        // b.mp4 changes and the output of c.mp4 goes missing
+        fs::write(dir.path().join("b.mp4"), "changed content").unwrap();
+        fs::remove_file(dir.path().join("c-rev.mp4")).unwrap();
+
+        let second = reverser.reverse_directory_incremental(dir.path(), &mut cache);
+        let statuses: Vec<_> = second
+            .into_iter()
+            .map(|(input, result)| (input, result.unwrap()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (
+                    dir.path().join("a.mp4"),
+                    BatchStatus::Skipped(dir.path().join("a-rev.mp4"))
+                ),
+                (
+                    dir.path().join("b.mp4"),
+                    BatchStatus::Reversed(dir.path().join("b-rev.mp4"))
+                ),
+                (
+                    dir.path().join("c.mp4"),
+                    BatchStatus::Reversed(dir.path().join("c-rev.mp4"))
+                ),
+            ]
+        );
+        assert_eq!(
+            cache.get(dir.path().join("b.mp4")).unwrap().size,
+            "changed content".len() as u64
+        );
+
+        // Without a cache entry, the overwrite policy still skips existing outputs
+        let skipping = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .overwrite(OverwritePolicy::Skip)
+            .build();
+        let third = skipping.reverse_directory_incremental(dir.path(), &mut InputCache::default());
+        assert!(third
+            .iter()
+            .all(|(_, result)| matches!(result, Ok(BatchStatus::Skipped(_)))));
+    }
+
+    #[test]
+    fn test_input_cache_round_trips_through_file() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("a.mp4");
+        fs::write(&input, "test content").unwrap();
+        let sidecar = dir.path().join("cache.json");
+
+        assert!(InputCache::load(&sidecar).unwrap().is_empty());
+
+        let mut cache = InputCache::default();
+        cache.insert(&input, InputSignature::of(&input).unwrap());
+        cache.save(&sidecar).unwrap();
+
+        assert_eq!(InputCache::load(&sidecar).unwrap(), cache);
+
+        fs::write(&sidecar, "not json").unwrap();
+        assert!(matches!(
+            InputCache::load(&sidecar),
+            Err(VideoError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_io_error_exposes_source() {
+        use std::error::Error as _;
+
+        let reverser = VideoReverser::new();
+        let mut results = reverser.reverse_directory("does-not-exist");
+        let err = results.pop().unwrap().1.unwrap_err();
+        let source = err.source().expect("io errors keep their cause");
+        let io = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_reverse_multiple() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("one").join("b.mp4");
+        let second = dir.path().join("a.mov");
+        fs::create_dir(dir.path().join("one")).unwrap();
+        fs::write(&first, "test content").unwrap();
+        fs::write(&second, "test content").unwrap();
+        let missing = dir.path().join("missing.mp4");
+
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        let inputs = vec![first.clone(), missing.clone(), second.clone()];
+        let results = reverser.reverse_multiple(&inputs);
+
+        // Input order is kept and the failure doesn't stop the last file
+        let paths: Vec<_> = results.iter().map(|(input, _)| input.clone()).collect();
+        assert_eq!(paths, inputs);
+        assert_eq!(
+            results[0].1.as_ref().unwrap(),
+            &dir.path().join("one").join("b-rev.mp4")
+        );
+        assert!(matches!(results[1].1, Err(VideoError::InvalidInput(_))));
+        assert_eq!(
+            results[2].1.as_ref().unwrap(),
+            &dir.path().join("a-rev.mov")
+        );
+
+        let out_dir = dir.path().join("out");
+        let results = reverser
+            .reverse_multiple_with(&[second], |input| out_dir.join(input.file_name().unwrap()));
+        assert_eq!(results[0].1.as_ref().unwrap(), &out_dir.join("a.mov"));
+    }
+
+    #[test]
+    fn test_reverse_directory_parallel() {
+        let dir = tempdir().unwrap();
+        for name in ["a.mp4", "b.mp4", "c.mp4", "d.mp4"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+
+        // A panic while reversing c.mp4 must not take down the other workers
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if args.iter().any(|a| a.ends_with("c.mp4")) && !args.contains(&"-version") {
+                panic!("runner exploded");
+            }
+            Ok(mock_success())
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let results = reverser.reverse_directory_parallel(dir.path(), 2);
+
+        let inputs: Vec<_> = results.iter().map(|(input, _)| input.clone()).collect();
+        assert_eq!(
+            inputs,
+            ["a.mp4", "b.mp4", "c.mp4", "d.mp4"].map(|name| dir.path().join(name))
+        );
+        for (input, result) in &results {
+            if input.ends_with("c.mp4") {
+                match result {
+                    Err(VideoError::WorkerPanic(msg)) => assert_eq!(msg, "runner exploded"),
+                    other => panic!("Expected WorkerPanic, got {other:?}"),
+                }
+            } else {
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_thread_limit() {
+        let limited = VideoReverser::builder().threads(3).build();
+        let args = limited.build_ffmpeg_args("in.mp4", "out.mp4");
+        assert!(args.windows(2).any(|w| w == ["-threads", "3"]));
+        assert!(args.windows(2).any(|w| w == ["-x264-params", "threads=3"]));
+
+        // Other encoders don't take x264's parameters
+        let vp9 = VideoReverser::builder()
+            .threads(3)
+            .encoder("libvpx-vp9")
+            .build();
+        let args = vp9.build_ffmpeg_args("in.webm", "out.webm");
+        assert!(args.windows(2).any(|w| w == ["-threads", "3"]));
+        assert!(!args.iter().any(|a| a == "-x264-params"));
+
+        // Nor do the encoders ffmpeg picks for containers other than MP4, MOV and MKV
+        let default = VideoReverser::builder().threads(2).build();
+        for output in ["out.avi", "out.webm"] {
+            let args = default.build_ffmpeg_args("in.mp4", output);
+            assert!(args.windows(2).any(|w| w == ["-threads", "2"]), "{output}");
+            assert!(!args.iter().any(|a| a == "-x264-params"), "{output}");
+        }
+
+        assert!(matches!(
+            VideoReverser::builder().threads(0).validate(),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_webm_defaults() {
+        let args = VideoReverser::new().build_ffmpeg_args("in.webm", "out.webm");
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libvpx-vp9"]));
+        assert!(args.windows(2).any(|w| w == ["-crf", "31"]));
+        assert!(args.windows(2).any(|w| w == ["-b:v", "0"]));
+        assert!(args.windows(2).any(|w| w == ["-c:a", "libopus"]));
+
+        // Other containers keep ffmpeg's defaults
+        let args = VideoReverser::new().build_ffmpeg_args("in.mp4", "out.mp4");
+        assert!(!args.iter().any(|a| a == "-c:v" || a == "-crf"));
+
+        // Explicit options win over the container's defaults
+        let args = VideoReverser::builder()
+            .crf(20)
+            .encoder("libvpx")
+            .build()
+            .build_ffmpeg_args("in.webm", "out.webm");
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libvpx"]));
+        assert!(!args.iter().any(|a| a == "libvpx-vp9"));
+        let args = VideoReverser::builder()
+            .crf(20)
+            .build()
+            .build_ffmpeg_args("in.webm", "out.webm");
+        assert!(args.windows(2).any(|w| w == ["-crf", "20"]));
+        let args = VideoReverser::builder()
+            .target_bitrate(800)
+            .build()
+            .build_ffmpeg_args("in.webm", "out.webm");
+        assert!(args.windows(2).any(|w| w == ["-b:v", "800k"]));
+        assert!(!args.iter().any(|a| a == "-crf"));
+    }
+
+    #[test]
+    fn test_parallel_batch_divides_cores() {
+        let dir = tempdir().unwrap();
+        for name in ["a.mp4", "b.mp4", "c.mp4"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+        let thread_counts = |builder: VideoReverserBuilder| {
+            let runner = MockCommandRunner::new(mock_ok);
+            let calls = runner.calls.clone();
+            let results = builder
+                .runner(Box::new(runner))
+                .build()
+                .reverse_directory_parallel(dir.path(), 2);
+            assert!(results.iter().all(|(_, result)| result.is_ok()));
+            let calls = calls.lock().unwrap();
+            calls
+                .iter()
+                .filter(|(_, args)| args.contains(&"-vf".to_string()))
+                .map(|(_, args)| {
+                    let i = args.iter().position(|a| a == "-threads").unwrap();
+                    args[i + 1].clone()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let cores = std::thread::available_parallelism().unwrap().get();
+        let share = (cores / 2).max(1).to_string();
+        assert_eq!(thread_counts(VideoReverser::builder()), vec![share; 3]);
+        assert_eq!(
+            thread_counts(VideoReverser::builder().threads(1)),
+            vec!["1"; 3]
+        );
+    }
+
+    #[test]
+    fn test_default_concurrency_is_positive() {
+        assert!(default_concurrency() >= 1);
+    }
+
+    #[test]
+    fn test_probe_rejects_file_without_video() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, _| {
+            if program == "ffprobe" {
+                Ok(mock_stdout("[STREAM]\ncodec_type=audio\n[/STREAM]\n"))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(matches!(result, Err(VideoError::NotAVideo(_))));
+        // ffmpeg itself never ran
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_reverse_stream_uses_pipes() {
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        reverser.reverse_stream("matroska").unwrap();
+
+        let calls = calls.lock().unwrap();
+        // ffmpeg -version, then the reversal itself; nothing probes the input
+        assert_eq!(calls.len(), 2);
+        let args = &calls[1].1;
+        assert_eq!(args[..2], ["-i", "pipe:0"]);
+        assert_eq!(args[args.len() - 4..], ["-f", "matroska", "-y", "pipe:1"]);
+    }
+
+    #[test]
+    fn test_reverse_stream_rejects_unstreamable_format() {
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let result = reverser.reverse_stream("mp4");
+
+        assert!(matches!(result, Err(VideoError::InvalidInput(msg)) if msg.contains("mp4")));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reverse_video_detailed_probes_output() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if program == "ffprobe" && args.contains(&"-select_streams") {
+                return Ok(mock_stdout(
+                    "width=1920\nheight=1080\nduration=192.500000\n",
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let outcome = reverser.reverse_video_detailed(&file_path).unwrap();
+
+        assert_eq!(outcome.path, dir.path().join("test-rev.mp4"));
+        assert_eq!(outcome.duration, Some(Duration::from_secs_f64(192.5)));
+        assert_eq!(outcome.resolution, Some((1920, 1080)));
+        // The mock writes "reversed" to the output
+        assert_eq!(outcome.size, 8);
+        assert!(outcome.elapsed > Duration::ZERO);
+        assert!(!outcome.overwrote_existing);
+        assert!(
+            reverser
+                .reverse_video_detailed(&file_path)
+                .unwrap()
+                .overwrote_existing
+        );
+
+        let timed = ReverseOutcome {
+            elapsed: Duration::from_secs(50),
+            ..outcome
+        };
+        assert_eq!(timed.realtime_factor(), Some(192.5 / 50.0));
+        assert!(timed.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_reverse_audio_to() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let output = dir.path().join("test-rev.wav");
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        assert_eq!(
+            reverser.reverse_audio_to(&file_path, &output).unwrap(),
+            output
+        );
+        assert!(output.exists());
+
+        let calls = calls.lock().unwrap();
+        let (_, args) = calls
+            .iter()
+            .find(|(program, args)| program == "ffmpeg" && args.contains(&"-vn".to_string()))
+            .unwrap();
+        assert!(args.windows(2).any(|w| w == ["-af", "areverse"]));
+        assert!(!args.iter().any(|arg| arg == "-vf" || arg == "-c:v"));
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_silent_streams());
+            }
+            mock_ok(program, args)
+        });
+        let silent = VideoReverser::new_with_runner(Box::new(runner));
+        assert!(matches!(
+            silent.reverse_audio_to(&file_path, dir.path().join("silent.wav")),
+            Err(VideoError::NoAudioStream(_))
+        ));
+    }
+
+    #[test]
+    fn test_metrics_sink() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = recorded.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .overwrite(OverwritePolicy::Never)
+            .metrics(move |metrics: &ReverseMetrics| {
+                sink.lock().unwrap().push((
+                    metrics.input.to_path_buf(),
+                    metrics.input_size,
+                    metrics.output_size,
+                    metrics
+                        .error
+                        .map(|err| matches!(err, VideoError::OutputExists(_))),
+                ))
+            })
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+        assert!(reverser.reverse_video(&file_path).is_err());
+
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![
+                (file_path.clone(), Some(12), Some(8), None),
+                (file_path.clone(), Some(12), None, Some(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metrics_cover_streams_and_concatenations() {
+        let dir = tempdir().unwrap();
+        let inputs: Vec<_> = ["part1.mp4", "part2.mp4"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for input in &inputs {
+            fs::write(input, "test content").unwrap();
+        }
+        let output = dir.path().join("whole-rev.mp4");
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"-show_format") {
+                return Ok(mock_stdout(
+                    r#"{"streams": [{"codec_name": "h264", "codec_type": "video"}]}"#,
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = recorded.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .metrics(move |metrics: &ReverseMetrics| {
+                sink.lock().unwrap().push((
+                    metrics.input.to_path_buf(),
+                    metrics.output.to_path_buf(),
+                    metrics.output_size,
+                    metrics.error.is_some(),
+                ))
+            })
+            .build();
+
+        reverser.reverse_stream("matroska").unwrap();
+        reverser.reverse_concat(&inputs, &output).unwrap();
+        assert!(reverser.reverse_stream("mp4").is_err());
+
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![
+                ("pipe:0".into(), "pipe:1".into(), None, false),
+                (inputs[0].clone(), output.clone(), Some(8), false),
+                ("pipe:0".into(), "pipe:1".into(), None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // The first conversion fails for lack of resources, the second succeeds
+        let failures = Arc::new(AtomicUsize::new(1));
+        let remaining = failures.clone();
+        let runner = MockCommandRunner::new(move |program, args| {
+            if program == "ffmpeg" && args.contains(&"-i") {
+                if remaining.fetch_sub(1, Ordering::Relaxed) > 0 {
+                    return Ok(mock_failure("Cannot allocate memory"));
+                }
+                return Ok(mock_success());
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .retries(2)
+            .retry_delay(Duration::ZERO)
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+        let conversions = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, args)| args.contains(&"-i".to_string()))
+            .count();
+        assert_eq!(conversions, 2);
+    }
+
+    #[test]
+    fn test_corrupt_input_is_not_retried() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if program == "ffmpeg" && args.contains(&"-i") {
+                return Ok(mock_failure("moov atom not found"));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .retries(3)
+            .retry_delay(Duration::ZERO)
+            .build();
+
+        assert!(matches!(
+            reverser.reverse_video(&file_path),
+            Err(VideoError::ProcessingError {
+                kind: FailureKind::InvalidInput,
+                ..
+            })
+        ));
+        assert_eq!(calls.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_reverse_video_detailed_collects_warnings() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                Ok(mock_silent_streams())
+            } else if program == "ffprobe" {
+                Ok(mock_failure("test-rev.mp4: No such file or directory"))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .extra_args(vec![
+                "-metadata".into(),
+                "title=x".into(),
+                "second.mp4".into(),
+            ])
+            .build();
+
+        let outcome = reverser.reverse_video_detailed(&file_path).unwrap();
+
+        assert_eq!(
+            outcome.warnings,
+            [
+                Warning::SuspiciousExtraArgument("second.mp4".to_string()),
+                Warning::NoAudioStream,
+                Warning::OutputNotProbed,
+            ]
+        );
+        assert_eq!(
+            outcome.warnings[0].to_string(),
+            "extra ffmpeg argument \"second.mp4\" looks like an additional output"
+        );
+    }
+
+    #[test]
+    fn test_reverse_outcome_tolerates_missing_fields() {
+        let outcome = ReverseOutcome::parse(
+            PathBuf::from("out.mp4"),
+            3,
+            Duration::from_secs(1),
+            "duration=N/A\n",
+        );
+        assert_eq!(outcome.duration, None);
+        assert_eq!(outcome.resolution, None);
+        assert_eq!(outcome.size, 3);
+        assert_eq!(outcome.realtime_factor(), None);
+    }
+
+    #[test]
+    fn test_parse_ffmpeg_version() {
+        let line = |version: &str| format!("ffmpeg version {} Copyright (c) 2000-2023\n", version);
+        assert_eq!(parse_ffmpeg_version(&line("6.1.1")), Some((6, 1)));
+        assert_eq!(parse_ffmpeg_version(&line("n7.0")), Some((7, 0)));
+        assert_eq!(
+            parse_ffmpeg_version(&line("4.4.2-0ubuntu0.22.04.1")),
+            Some((4, 4))
+        );
+        assert_eq!(parse_ffmpeg_version(&line("5-static")), Some((5, 0)));
+        assert_eq!(parse_ffmpeg_version(&line("N-112345-g1a2b3c")), None);
+        assert_eq!(parse_ffmpeg_version(""), None);
+    }
+
+    #[test]
+    fn test_min_ffmpeg_version_rejects_old_builds() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args == ["-version"] {
+                return Ok(mock_stdout("ffmpeg version 4.4.2-0ubuntu0.22.04.1\n"));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .min_ffmpeg_version(5, 0)
+            .build();
+
+        assert_eq!(reverser.ffmpeg_version().unwrap(), Some((4, 4)));
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(
+            result,
+            Err(VideoError::FFmpegTooOld {
+                found: (4, 4),
+                required: (5, 0)
+            })
+        ));
+        // Only the two -version calls ran
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_verify_output_rejects_decode_errors() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        let output_path = dir.path().join("test-rev.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(&output_path, "previous").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.ends_with(&["-f", "null", "-"]) {
+                return Ok(std::process::Output {
+                    stderr: b"[h264 @ 0x5581] error while decoding MB 10 4\n".to_vec(),
+                    ..mock_success()
+                });
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .verify_output(true)
+            .build();
+
+        let err = reverser.reverse_video(&file_path).unwrap_err();
+        assert!(matches!(&err, VideoError::OutputCorrupt { path, log }
+            if *path == output_path && log.contains("error while decoding")));
+        assert!(err.to_string().contains("error while decoding MB 10 4"));
+        // The decoded file was the temp output, which never replaced the old one
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.last().unwrap().1[..3], ["-v", "error", "-i"]);
+        assert_ne!(calls.last().unwrap().1[3], output_path.to_str().unwrap());
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), "previous");
+    }
+
+    #[test]
+    fn test_strict_ffmpeg_check() {
+        assert_eq!(
+            identify_ffmpeg("ffmpeg version 6.1.1 Copyright (c) 2000-2023\n"),
+            Ok(())
+        );
+        assert!(
+            identify_ffmpeg("avconv version 12.3, Copyright (c) 2000-2018\n")
+                .unwrap_err()
+                .contains("avconv")
+        );
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let wrapper = || {
+            MockCommandRunner::new(|program, args| {
+                if args == ["-version"] {
+                    return Ok(mock_stdout("my-wrapper 1.0\n"));
+                }
+                mock_ok(program, args)
+            })
+        };
+
+        let lenient = VideoReverser::new_with_runner(Box::new(wrapper()));
+        assert!(lenient.reverse_video(&file_path).is_ok());
+        let strict = VideoReverser::builder()
+            .runner(Box::new(wrapper()))
+            .strict_ffmpeg_check(true)
+            .build();
+        let err = strict.reverse_video(&file_path).unwrap_err();
+        assert!(
+            matches!(&err, VideoError::NotFFmpeg { reason, .. } if reason.contains("my-wrapper"))
+        );
+    }
+
+    #[test]
+    fn test_env_defaults() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+        let reverser = VideoReverserBuilder::default()
+            .env_defaults(env(&[
+                (CRF_ENV, "23"),
+                (PRESET_ENV, "veryfast"),
+                (OVERWRITE_ENV, "Skip"),
+                (EXTENSIONS_ENV, "mp4, .MTS"),
+            ]))
+            .build();
+        assert_eq!(reverser.crf, Some(23));
+        assert_eq!(reverser.preset.as_deref(), Some("veryfast"));
+        assert_eq!(reverser.overwrite, OverwritePolicy::Skip);
+        assert_eq!(reverser.allowed_extensions, ["mp4", "MTS"]);
+
+        // Unparseable values keep the defaults, and builder calls win over the environment
+        let reverser = VideoReverserBuilder::default()
+            .env_defaults(env(&[
+                (CRF_ENV, "80"),
+                (OVERWRITE_ENV, "sometimes"),
+                (PRESET_ENV, "fast"),
+            ]))
+            .preset("slow")
+            .build();
+        assert_eq!(reverser.crf, None);
+        assert_eq!(reverser.overwrite, OverwritePolicy::Always);
+        assert_eq!(reverser.preset.as_deref(), Some("slow"));
+    }
+
+    #[test]
+    fn test_custom_temp_dir() {
+        let dir = tempdir().unwrap();
+        let scratch = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .target_bitrate(2500)
+            .two_pass(true)
+            .temp_dir(scratch.path())
+            .build();
+
+        let output = reverser.reverse_video(&file_path).unwrap();
+        assert_eq!(fs::read_to_string(output).unwrap(), "reversed");
+        let calls = calls.lock().unwrap();
+        let args = &calls.last().unwrap().1;
+        let passlog = args.iter().position(|a| a == "-passlogfile").unwrap();
+        assert!(Path::new(&args[passlog + 1]).starts_with(scratch.path()));
+        assert!(Path::new(args.last().unwrap()).starts_with(scratch.path()));
+        assert_eq!(fs::read_dir(scratch.path()).unwrap().count(), 0);
+        drop(calls);
+
+        let missing = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .temp_dir(dir.path().join("missing"))
+            .build();
+        assert!(matches!(
+            missing.reverse_video(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_two_pass_encode() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            // Stand in for libx264's statistics file
+            if let Some(at) = args.iter().position(|a| *a == "-passlogfile") {
+                fs::write(format!("{}-0.log", args[at + 1]), "stats").unwrap();
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .target_bitrate(2500)
+            .two_pass(true)
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+
+        let calls = calls.lock().unwrap();
+        let [first, second] = &calls[calls.len() - 2..] else {
+            unreachable!()
+        };
+        let tail = |args: &[String], n: usize| args[args.len() - n..].to_vec();
+        assert!(first.1.windows(2).any(|pair| pair == ["-b:v", "2500k"]));
+        assert_eq!(first.1[8..11], ["-pass", "1", "-passlogfile"]);
+        assert!(first.1.contains(&"-an".to_string()));
+        assert_eq!(tail(&first.1, 4), ["-f", "null", "-y", NULL_DEVICE]);
+        assert_eq!(
+            second.1[8..12],
+            ["-pass", "2", "-passlogfile", first.1[11].as_str()]
+        );
+        assert!(!second.1.contains(&"-an".to_string()));
+        // Both passes shared the statistics, which are gone afterwards
+        assert!(!Path::new(&format!("{}-0.log", first.1[11])).exists());
+
+        let conflicting = VideoReverser::builder()
+            .target_bitrate(1000)
+            .crf(20)
+            .build();
+        assert!(matches!(
+            conflicting.reverse_video(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_validate() {
+        assert!(VideoReverser::builder().validate().is_ok());
+        assert!(VideoReverser::builder()
+            .target_bitrate(1000)
+            .two_pass(true)
+            .validate()
+            .is_ok());
+
+        let conflicts = [
+            VideoReverser::builder().two_pass(true),
+            VideoReverser::builder()
+                .mode(ReverseMode::AudioOnly)
+                .target_bitrate(1000),
+            VideoReverser::builder().audio_options(AudioOptions {
+                drop: true,
+                normalize: true,
+                ..AudioOptions::default()
+            }),
+            VideoReverser::builder().fade(Duration::ZERO),
+        ];
+        for builder in conflicts {
+            let err = builder.validate().unwrap_err();
+            assert!(matches!(err, VideoError::InvalidOptions(_)));
+            assert!(err.to_string().starts_with("Invalid options: "));
+        }
+    }
+
+    #[test]
+    fn test_crf_and_preset_args() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder().crf(18).preset("slow").build();
+        let args = reverser.plan_command(&file_path).unwrap().args;
+        let crf = args.iter().position(|a| a == "-crf").unwrap();
+        assert_eq!(args[crf + 1..crf + 4], ["18", "-preset", "slow"]);
+
+        let nvenc = VideoReverser::builder()
+            .encoder("h264_nvenc")
+            .crf(18)
+            .build();
+        let args = nvenc.plan_command(&file_path).unwrap().args;
+        assert!(!args.iter().any(|a| a == "-crf"));
+    }
+
+    #[test]
+    fn test_invalid_crf_and_preset_rejected() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let high_crf = VideoReverser::builder().crf(52).build();
+        assert!(matches!(
+            high_crf.plan_command(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+        let bad_preset = VideoReverser::builder().preset("warp").build();
+        assert!(matches!(
+            bad_preset.plan_command(&file_path),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_encoder_adds_codec_and_hwaccel() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"-encoders") {
+                return Ok(mock_stdout(
+                    " V....D libx264   libx264 H.264\n V....D h264_nvenc   NVIDIA NVENC H.264 encoder\n",
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .encoder("h264_nvenc")
+            .build();
+
+        reverser.reverse_video(&file_path).unwrap();
+
+        let calls = calls.lock().unwrap();
+        let args = &calls[3].1;
+        assert_eq!(args[..2], ["-hwaccel", "cuda"]);
+        let codec = args.iter().position(|a| a == "-c:v").unwrap();
+        assert_eq!(args[codec + 1], "h264_nvenc");
+    }
+
+    #[test]
+    fn test_missing_encoder_is_invalid_input() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if args.contains(&"-encoders") {
+                return Ok(mock_stdout(" V....D libx264   libx264 H.264\n"));
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .encoder("h264_nvenc")
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(result, Err(VideoError::InvalidInput(msg)) if msg.contains("h264_nvenc")));
+    }
+
+    #[test]
+    fn test_extra_args_precede_output() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder()
+            .extra_args(vec!["-movflags".to_string(), "+faststart".to_string()])
+            .build();
+
+        let command = reverser.plan_command(&file_path).unwrap();
+        let args = &command.args;
+        assert_eq!(
+            args[args.len() - 4..args.len() - 2],
+            ["-movflags", "+faststart"]
+        );
+        assert_eq!(
+            args.last().unwrap(),
+            dir.path().join("test-rev.mp4").as_os_str()
+        );
+    }
+
+    #[test]
+    fn test_aspect_crop() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let video_filter = |rotation: u32, ratio: (u32, u32)| {
+            let runner = MockCommandRunner::new(move |program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(&format!(
+                        "[STREAM]\nindex=0\ncodec_type=video\nwidth=1920\nheight=1080\n\
+                         TAG:rotate={rotation}\n[/STREAM]\n"
+                    )));
+                }
+                mock_ok(program, args)
+            });
+            let calls = runner.calls.clone();
+            let reverser = VideoReverser::builder()
+                .runner(Box::new(runner))
+                .aspect_crop(ratio.0, ratio.1)
+                .build();
+            fs::remove_file(dir.path().join("test-rev.mp4")).ok();
+            reverser.reverse_video(&file_path)?;
+            let calls = calls.lock().unwrap();
+            let (_, args) = calls
+                .iter()
+                .find(|(_, args)| args.contains(&"-vf".to_string()))
+                .unwrap();
+            let index = args.iter().position(|arg| arg == "-vf").unwrap();
+            Ok::<_, VideoError>(args[index + 1].clone())
+        };
+
+        // 1080 * 9 / 16 = 607.5, rounded down to an even width
+        assert_eq!(video_filter(0, (9, 16)).unwrap(), "crop=606:1080,reverse");
+        assert_eq!(video_filter(0, (1, 1)).unwrap(), "crop=1080:1080,reverse");
+        // A portrait recording is turned upright first
+        assert_eq!(
+            video_filter(90, (9, 16)).unwrap(),
+            "transpose=clock,crop=1080:1920,reverse"
+        );
+        assert!(matches!(
+            video_filter(0, (1, 2000)),
+            Err(VideoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            VideoReverser::builder().aspect_crop(0, 16).validate(),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_video_filters_follow_reverse() {
+        let filters = |filters: &[&str]| filters.iter().map(|f| f.to_string()).collect();
+        let args = VideoReverser::builder()
+            .video_filters(filters(&["eq=contrast=1.2", "lut3d=grade.cube"]))
+            .speed(2.0)
+            .build()
+            .build_ffmpeg_args("in.mp4", "out.mp4");
+        assert!(args.windows(2).any(|w| w
+            == [
+                "-vf",
+                "reverse,eq=contrast=1.2,lut3d=grade.cube,setpts=PTS/2"
+            ]));
+
+        // The copied video of an audio-only reversal isn't filtered
+        let args = VideoReverser::builder()
+            .video_filters(filters(&["eq=contrast=1.2"]))
+            .mode(ReverseMode::AudioOnly)
+            .build()
+            .build_ffmpeg_args("in.mp4", "out.mp4");
+        assert!(args.windows(2).any(|w| w == ["-c:v", "copy"]));
+
+        for bad in ["", "eq=contrast=1.2;[0:v]hflip", "eq[out]"] {
+            assert!(matches!(
+                VideoReverser::builder()
+                    .video_filters(filters(&[bad]))
+                    .validate(),
+                Err(VideoError::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_empty_input_fails_before_ffmpeg() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("empty.mp4");
+        fs::write(&file_path, "").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        match reverser.reverse_video(&file_path) {
+            Err(VideoError::InvalidInput(message)) => assert_eq!(message, "Input file is empty"),
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_extra_args_reject_additional_input() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .extra_args(vec!["-i".to_string(), "other.mp4".to_string()])
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(matches!(result, Err(VideoError::InvalidOptions(_))));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_maps_to_cancelled() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if program == "ffmpeg" && args.contains(&"-i") {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "command cancelled",
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .ffmpeg_path("ffmpeg")
+            .cancel_token(Arc::new(AtomicBool::new(true)))
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(matches!(result, Err(VideoError::Cancelled)));
+        assert!(!dir.path().join("test-rev.mp4").exists());
+    }
+
+    #[test]
+    fn test_timeout_maps_to_error_and_cleans_up() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if program == "ffmpeg" && args.contains(&"-i") {
+                // Leave a partial file behind, as a killed ffmpeg would
+                fs::write(args.last().unwrap(), "partial")?;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "command timed out",
+                ));
+            }
+            mock_ok(program, args)
+        });
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .ffmpeg_path("ffmpeg")
+            .timeout(Duration::from_secs(5))
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(matches!(result, Err(VideoError::Timeout(t)) if t == Duration::from_secs(5)));
+        let remaining: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(remaining.len(), 1, "only the input should remain");
+    }
+
+    #[test]
+    fn test_overwrite_never_rejects_existing_output() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(dir.path().join("test-rev.mp4"), "old").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .overwrite(OverwritePolicy::Never)
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+
+        assert!(matches!(result, Err(VideoError::OutputExists(_))));
+        assert!(calls.lock().unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("test-rev.mp4")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn test_overwrite_skip_returns_existing_output() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        let other_path = dir.path().join("other.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(&other_path, "test content").unwrap();
+        fs::write(dir.path().join("test-rev.mp4"), "old").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .overwrite(OverwritePolicy::Skip)
+            .build();
+
+        let skipped = reverser.reverse_video(&file_path).unwrap();
+        assert_eq!(skipped, dir.path().join("test-rev.mp4"));
+        assert!(calls.lock().unwrap().is_empty());
+
+        // Outputs that don't exist yet are still written, without letting ffmpeg overwrite
+        reverser.reverse_video(&other_path).unwrap();
+        let calls = calls.lock().unwrap();
+        let args = &calls[2].1;
+        assert_eq!(args[args.len() - 2], "-n");
+    }
+
+    #[test]
+    fn test_probe_rejects_unreadable_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "not really a video").unwrap();
+
+        let runner = MockCommandRunner::new(|program, _| {
+            if program == "ffprobe" {
+                Ok(mock_failure(
+                    "test.mp4: Invalid data found when processing input\n",
+                ))
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        match reverser.reverse_video(&file_path) {
+            Err(VideoError::NotAVideo(msg)) => {
+                assert!(msg.ends_with("Invalid data found when processing input"))
+            }
+            other => panic!("Expected NotAVideo, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_probe_validation_can_be_disabled() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|_, _| Ok(mock_success()));
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .probe_validation(false)
+            .build();
+
+        assert!(reverser.reverse_video(&file_path).is_ok());
+        assert!(calls.lock().unwrap().iter().all(|(p, _)| p != "ffprobe"));
+    }
+
+    #[test]
+    fn test_silent_video_omits_audio_args() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("screen.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                Ok(mock_silent_streams())
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        reverser.reverse_video(&file_path).unwrap();
+        let calls = calls.lock().unwrap();
+        let args = &calls[2].1;
+        assert!(args.contains(&"reverse".to_string()));
+        assert!(!args.iter().any(|arg| arg == "-af" || arg == "-c:a"));
+    }
+
+    #[test]
+    fn test_audio_only_on_silent_video_fails() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("screen.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                Ok(mock_silent_streams())
+            } else {
+                Ok(mock_success())
+            }
+        });
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .mode(ReverseMode::AudioOnly)
+            .build();
+
+        match reverser.reverse_video(&file_path) {
+            Err(VideoError::NoAudioStream(path)) => assert_eq!(path, file_path),
+            other => panic!("Expected NoAudioStream, got {other:?}"),
+        }
+        // ffmpeg itself never ran
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_output_written_via_temp_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let output_path = dir.path().join("test-rev.mp4");
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        reverser.reverse_video(&file_path).unwrap();
+
+        // ffmpeg was pointed at a hidden sibling with the same extension
+        let calls = calls.lock().unwrap();
+        let target = PathBuf::from(calls.last().unwrap().1.last().unwrap());
+        assert_ne!(target, output_path);
+        assert_eq!(target.parent(), Some(dir.path()));
+        assert_eq!(target.extension().unwrap(), "mp4");
+        assert!(target
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with('.'));
+
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), "reversed");
+        assert!(!target.exists());
+        // Neither the temp file nor the writability probe is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_output_dir_fails_before_ffmpeg() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        if fs::write(locked.join("probe"), "").is_ok() {
+            // Permissions don't apply to root
+            return;
+        }
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        let result = reverser.reverse_video_to(&file_path, locked.join("out.mp4"));
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        match result {
+            Err(VideoError::OutputNotWritable(path)) => assert_eq!(path, locked),
+            other => panic!("Expected OutputNotWritable, got {other:?}"),
+        }
+        // Only the version check and the input probe ran
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_temp_file_removed_on_failure() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // ffmpeg writes part of the output, then fails
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if !args.contains(&"-version") {
+                fs::write(args.last().unwrap(), "partial").unwrap();
+                return Ok(mock_failure("No space left on device"));
+            }
+            Ok(mock_success())
+        });
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+
+        assert!(reverser.reverse_video(&file_path).is_err());
+
+        let remaining: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(remaining, vec![OsString::from("test.mp4")]);
+    }
+
+    #[test]
+    fn test_audio_options_build_filter_chain() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let reverser = VideoReverser::builder()
+            .audio_options(AudioOptions {
+                volume: Some(0.5),
+                normalize: true,
+                channels: Some(2),
+                sample_rate: Some(48000),
+                ..AudioOptions::default()
+            })
+            .build();
+        let args = reverser.plan_command(&file_path).unwrap().args;
+
+        assert_eq!(
+            args[4..16],
+            [
+                "-af",
+                "areverse,volume=0.5,loudnorm",
+                "-ac",
+                "2",
+                "-ar",
+                "48000",
+                "-sn",
+                "-dn",
+                "-map_metadata",
+                "0",
+                "-y",
+                args[15].to_str().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_atempo_chain() {
+        assert_eq!(atempo_chain(1.5), ["atempo=1.5"]);
+        assert_eq!(atempo_chain(4.0), ["atempo=2", "atempo=2"]);
+        assert_eq!(atempo_chain(0.25), ["atempo=0.5", "atempo=0.5"]);
+        assert_eq!(atempo_chain(0.3), ["atempo=0.5", "atempo=0.6"]);
+        assert_eq!(atempo_chain(5.0), ["atempo=2", "atempo=2", "atempo=1.25"]);
+    }
+
+    #[test]
+    fn test_speed_retimes_video_and_audio() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let slow = VideoReverser::builder().speed(0.25).build();
+        let args = slow.plan_command(&file_path).unwrap().args;
+        assert_eq!(
+            args[2..6],
+            [
+                "-vf",
+                "reverse,setpts=PTS/0.25",
+                "-af",
+                "areverse,atempo=0.5,atempo=0.5"
+            ]
+        );
+
+        // Retiming needs a re-encode even when the video isn't reversed
+        let audio_only = VideoReverser::builder()
+            .mode(ReverseMode::AudioOnly)
+            .speed(2.0)
+            .build();
+        let args = audio_only.plan_command(&file_path).unwrap().args;
+        assert_eq!(args[2..4], ["-vf", "setpts=PTS/2"]);
+
+        for speed in [0.0, -1.0, f64::NAN] {
+            let invalid = VideoReverser::builder().speed(speed).build();
+            assert!(matches!(
+                invalid.plan_command(&file_path),
+                Err(VideoError::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rotated_input_is_turned_upright() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("portrait.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        // A phone clip shot in portrait: the display matrix says to turn it 90° clockwise
+        let phone = || {
+            MockCommandRunner::new(|program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(
+                        "[STREAM]\nindex=0\ncodec_name=h264\ncodec_type=video\n\
+                         [SIDE_DATA]\nside_data_type=Display Matrix\nrotation=-90\n[/SIDE_DATA]\n\
+                         [/STREAM]\n[STREAM]\nindex=1\ncodec_type=audio\n[/STREAM]\n",
+                    ));
+                }
+                mock_ok(program, args)
+            })
+        };
+        let last_args = |auto_rotate| {
+            let runner = phone();
+            let calls = runner.calls.clone();
+            VideoReverser::builder()
+                .runner(Box::new(runner))
+                .auto_rotate(auto_rotate)
+                .build()
+                .reverse_video(&file_path)
+                .unwrap();
+            let calls = calls.lock().unwrap();
+            calls.last().unwrap().1.clone()
+        };
+
+        let args = last_args(true);
+        assert_eq!(
+            args[..5],
+            [
+                "-noautorotate",
+                "-i",
+                file_path.to_str().unwrap(),
+                "-vf",
+                "transpose=clock,reverse"
+            ]
+        );
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-metadata:s:v:0", "rotate=0"]));
+
+        let args = last_args(false);
+        assert_eq!(args[2..4], ["-vf", "reverse"]);
+        assert!(!args.contains(&"-noautorotate".to_string()));
+
+        assert_eq!(quarter_turn("-90", -1.0), 90);
+        assert_eq!(quarter_turn("90", -1.0), 270);
+        assert_eq!(quarter_turn("180", 1.0), 180);
+        assert_eq!(rotation_filters(180), ["hflip", "vflip"]);
+    }
+
+    #[test]
+    fn test_variable_frame_rate_is_detected_and_normalized() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("screen.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        // A screen recording: a 60fps time base, but only about 24 frames a second
+        let recorder = || {
+            MockCommandRunner::new(|program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(
+                        "[STREAM]\nindex=0\ncodec_type=video\nr_frame_rate=60/1\n\
+                         avg_frame_rate=2343/100\n[/STREAM]\n\
+                         [STREAM]\nindex=1\ncodec_type=audio\nr_frame_rate=0/0\n\
+                         avg_frame_rate=0/0\n[/STREAM]\n",
+                    ));
+                }
+                mock_ok(program, args)
+            })
+        };
+
+        let outcome = VideoReverser::new_with_runner(Box::new(recorder()))
+            .reverse_video_detailed(&file_path)
+            .unwrap();
+        assert!(outcome.warnings.contains(&Warning::VariableFrameRate));
+
+        let runner = recorder();
+        let calls = runner.calls.clone();
+        let outcome = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .normalize_fps(30.0)
+            .build()
+            .reverse_video_detailed(&file_path)
+            .unwrap();
+        assert!(!outcome.warnings.contains(&Warning::VariableFrameRate));
+        let calls = calls.lock().unwrap();
+        let args = &calls
+            .iter()
+            .find(|(_, args)| args.contains(&"-vf".to_string()))
+            .unwrap()
+            .1;
+        assert_eq!(args[2..4], ["-vf", "fps=30,reverse"]);
+    }
+
+    #[test]
+    fn test_incompatible_pixel_format_is_converted_for_mp4() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("graded.mov");
+        let mkv_path = dir.path().join("graded.mkv");
+        fs::write(&file_path, "test content").unwrap();
+        fs::write(&mkv_path, "test content").unwrap();
+        let full_chroma = || {
+            MockCommandRunner::new(|program, args| {
+                if is_stream_probe(program, args) {
+                    return Ok(mock_stdout(
+                        "[STREAM]\nindex=0\ncodec_type=video\npix_fmt=yuv444p\n[/STREAM]\n\
+                         [STREAM]\nindex=1\ncodec_type=audio\npix_fmt=N/A\n[/STREAM]\n",
+                    ));
+                }
+                if args.contains(&"-encoders") {
+                    return Ok(mock_stdout(" V....D libvpx-vp9   libvpx VP9\n"));
+                }
+                mock_ok(program, args)
+            })
+        };
+        let pix_fmt = |builder: VideoReverserBuilder, input: &Path| {
+            let runner = full_chroma();
+            let calls = runner.calls.clone();
+            let outcome = builder
+                .runner(Box::new(runner))
+                .build()
+                .reverse_video_detailed(input)
+                .unwrap();
+            let calls = calls.lock().unwrap();
+            let args = &calls
+                .iter()
+                .find(|(_, args)| args.contains(&"-vf".to_string()))
+                .unwrap()
+                .1;
+            let format = args
+                .iter()
+                .position(|a| a == "-pix_fmt")
+                .map(|i| args[i + 1].clone());
+            (format, outcome.warnings)
+        };
+
+        let (format, warnings) = pix_fmt(VideoReverser::builder(), &file_path);
+        assert_eq!(format.as_deref(), Some("yuv420p"));
+        assert!(warnings.contains(&Warning::PixelFormatConverted {
+            from: "yuv444p".to_string()
+        }));
+
+        let (format, warnings) =
+            pix_fmt(VideoReverser::builder().pixel_format("yuv422p"), &file_path);
+        assert_eq!(format.as_deref(), Some("yuv422p"));
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        // Neither Matroska nor VP9 needs the conversion
+        for (builder, input) in [
+            (VideoReverser::builder(), &mkv_path),
+            (VideoReverser::builder().encoder("libvpx-vp9"), &file_path),
+        ] {
+            let (format, warnings) = pix_fmt(builder, input);
+            assert_eq!(format, None);
+            assert!(warnings.is_empty(), "{warnings:?}");
+        }
+    }
+
+    #[test]
+    fn test_repeat_plays_reversed_clip_several_times() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let looped = VideoReverser::builder().repeat(3).build();
+        let args = looped.plan_command(&file_path).unwrap().args;
+        assert_eq!(
+            args[2..6],
+            [
+                "-vf",
+                "reverse,split=3[r0][r1][r2];[r0][r1][r2]concat=n=3:v=1:a=0",
+                "-af",
+                "areverse,asplit=3[r0][r1][r2];[r0][r1][r2]concat=n=3:v=0:a=1"
+            ]
+        );
+
+        // The stream that isn't reversed still has to be repeated to keep in sync
+        let video_only = VideoReverser::builder()
+            .mode(ReverseMode::VideoOnly)
+            .repeat(2)
+            .build();
+        let args = video_only.plan_command(&file_path).unwrap().args;
+        assert_eq!(
+            args[4..6],
+            ["-af", "asplit=2[r0][r1];[r0][r1]concat=n=2:v=0:a=1"]
+        );
+
+        assert!(matches!(
+            VideoReverser::builder().repeat(0).validate(),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_fade_at_boundaries() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        // A 10s clip at double speed comes out 5s long
+        let reverser = |fade| {
+            let runner = MockCommandRunner::new(|program, args| {
+                if args.contains(&"format=duration") {
+                    Ok(mock_stdout("10.000000\n"))
+                } else {
+                    mock_ok(program, args)
+                }
+            });
+            VideoReverser::builder()
+                .runner(Box::new(runner))
+                .speed(2.0)
+                .fade(fade)
+                .build()
+        };
+
+        let args = reverser(Duration::from_secs(1))
+            .plan_command(&file_path)
+            .unwrap()
+            .args;
+        assert_eq!(
+            args[2..6],
+            [
+                "-vf",
+                "reverse,setpts=PTS/2,fade=t=in:st=0:d=1,fade=t=out:st=4:d=1",
+                "-af",
+                "areverse,atempo=2,afade=t=in:st=0:d=1,afade=t=out:st=4:d=1"
+            ]
+        );
+
+        let err = reverser(Duration::from_millis(2500))
+            .plan_command(&file_path)
+            .unwrap_err();
+        assert!(err.to_string().contains("too long"));
+        assert_eq!(
+            Boomerang { loops: 1 }.filter(
+                false,
+                Some(Fade {
+                    length: 0.5,
+                    clip: 4.0
+                })
+            ),
+            "[0:v]split=2[v0][v1];[v1]reverse[vr1];[v0][vr1]concat=n=2:v=1:a=0[vc];\
+             [vc]fade=t=in:st=0:d=0.5,fade=t=out:st=3.5:d=0.5[v]"
+        );
+    }
+
+    #[test]
+    fn test_audio_options_forward_and_drop() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let forward = VideoReverser::builder()
+            .audio_options(AudioOptions {
+                reverse: false,
+                ..AudioOptions::default()
+            })
+            .build();
+        let args = forward.plan_command(&file_path).unwrap().args;
+        assert_eq!(args[2..6], ["-vf", "reverse", "-c:a", "copy"]);
+
+        let dropped = VideoReverser::builder()
+            .audio_options(AudioOptions {
+                drop: true,
+                ..AudioOptions::default()
+            })
+            .build();
+        let args = dropped.plan_command(&file_path).unwrap().args;
+        assert_eq!(args[2..5], ["-vf", "reverse", "-an"]);
+    }
+
+    #[test]
+    fn test_audio_options_keep_forward() {
+        let args = |fit| {
+            VideoReverser::builder()
+                .audio_options(AudioOptions::keep_forward(fit))
+                .repeat(2)
+                .speed(0.5)
+                .build()
+                .build_ffmpeg_args("in.mp4", "out.mp4")
+        };
+
+        let shortest = args(AudioFit::Shortest);
+        assert!(!shortest
+            .iter()
+            .any(|arg| arg.to_string_lossy().contains("areverse")));
+        assert!(!shortest.iter().any(|arg| arg == "-af"));
+        assert!(shortest.windows(2).any(|w| w == ["-c:a", "copy"]));
+        assert!(shortest.iter().any(|arg| arg == "-shortest"));
+
+        let padded = args(AudioFit::Pad);
+        assert!(padded.windows(2).any(|w| w == ["-af", "apad"]));
+        assert!(padded.iter().any(|arg| arg == "-shortest"));
+
+        assert!(AudioOptions::keep_forward(AudioFit::Pad)
+            .validate(ReverseMode::AudioOnly)
+            .is_err());
+    }
+
+    #[test]
+    fn test_audio_options_conflicts_rejected() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(runner))
+            .audio_options(AudioOptions {
+                drop: true,
+                normalize: true,
+                ..AudioOptions::default()
+            })
+            .build();
+
+        let result = reverser.reverse_video(&file_path);
+        assert!(matches!(result, Err(VideoError::InvalidOptions(_))));
+        assert!(calls.lock().unwrap().is_empty());
+
+        let silent = AudioOptions {
+            drop: true,
+            ..AudioOptions::default()
+        };
+        assert!(silent.validate(ReverseMode::AudioOnly).is_err());
+        let zero_volume = AudioOptions {
+            volume: Some(0.0),
+            ..AudioOptions::default()
+        };
+        assert!(zero_volume.validate(ReverseMode::Both).is_err());
+    }
+
+    #[test]
+    fn test_ffmpeg_process_failure_mock() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        // Mock runner that fails on conversion but passes version check
+        let runner = MockCommandRunner::new(|program, args| {
+            if is_stream_probe(program, args) {
+                return Ok(mock_video_streams());
+            }
+            if args.contains(&"-version") {
+                Ok(mock_success())
+            } else {
+                Ok(mock_failure("Conversion failed"))
+            }
+        });
+
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video(&file_path);
+
+        match result {
+            Err(VideoError::ProcessingError {
+                kind,
+                code,
+                message,
+                stderr,
+                command,
+            }) => {
+                assert_eq!(kind, FailureKind::Other);
+                assert_eq!(code, Some(1));
+                assert_eq!(message, "Conversion failed");
+                assert_eq!(stderr, "Conversion failed");
+                // The whole invocation, ready to paste into a shell
+                assert!(command.starts_with("ffmpeg "), "{command}");
+                assert!(
+                    command.contains(&format!(" -i {} ", file_path.display())),
+                    "{command}"
+                );
+                assert!(command.contains(" -vf reverse "), "{command}");
+            }
+            _ => panic!("Expected ProcessingError"),
+        }
+    }
+
+    #[test]
+    fn test_processing_error_reports_exit_code() {
+        let failed = VideoError::ProcessingError {
+            kind: FailureKind::InvalidInput,
+            code: Some(69),
+            message: "moov atom not found".to_string(),
+            stderr: String::new(),
+            command: String::new(),
+        };
+        assert_eq!(failed.exit_code(), Some(69));
+        assert_eq!(
+            failed.to_string(),
+            "Failed to process video: moov atom not found (exit code 69)"
+        );
+
+        let killed = VideoError::ProcessingError {
+            kind: FailureKind::Other,
+            code: None,
+            message: "ffmpeg exited with signal: 9 (SIGKILL)".to_string(),
+            stderr: String::new(),
+            command: String::new(),
+        };
+        assert_eq!(killed.exit_code(), None);
+        assert!(killed.to_string().ends_with("(killed by a signal)"));
+        assert_eq!(VideoError::Cancelled.exit_code(), None);
+    }
+
+    #[test]
+    fn test_processing_error_classification() {
+        let cases = [
+            (
+                "input.mp4: Invalid data found when processing input\n",
+                FailureKind::InvalidInput,
+            ),
+            (
+                "[mov,mp4] moov atom not found\ninput.mp4: Invalid data found when processing input\n",
+                FailureKind::InvalidInput,
+            ),
+            ("Unknown encoder 'libx265'\n", FailureKind::CodecNotFound),
+            (
+                "out.mp4: Read-only file system\nConversion failed!\n",
+                FailureKind::OutputWrite,
+            ),
+            (
+                "av_interleaved_write_frame(): No space left on device\n",
+                FailureKind::OutputWrite,
+            ),
+            ("something unexpected\n", FailureKind::Other),
+        ];
+        for (stderr, expected) in cases {
+            assert_eq!(FailureKind::classify(stderr), expected, "{stderr}");
+        }
+    }
+
+    #[test]
+    fn test_last_meaningful_line_skips_summary() {
+        let stderr = "ffmpeg version 6.0\n\nout.mp4: Permission denied\nConversion failed!\n";
+        assert_eq!(
+            last_meaningful_line(stderr),
+            Some("out.mp4: Permission denied")
+        );
+        assert_eq!(
+            last_meaningful_line("Conversion failed!\n"),
+            Some("Conversion failed!")
+        );
+        assert_eq!(last_meaningful_line("\n  \n"), None);
+    }
+
+    #[test]
+    fn test_reverse_video_to_custom_output() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let output_path = dir.path().join("out").join("nested").join("reversed.mp4");
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video_to(&file_path, &output_path);
+
+        assert_eq!(result.unwrap(), output_path);
+        assert!(output_path.is_file());
+        // ffmpeg writes into the destination directory before the final rename
+        let calls = calls.lock().unwrap();
+        let target = PathBuf::from(calls.last().unwrap().1.last().unwrap());
+        assert_eq!(target.parent(), output_path.parent());
+    }
+
+    #[test]
+    fn test_reverse_video_to_same_path_rejected() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let runner = MockCommandRunner::new(mock_ok);
+        let calls = runner.calls.clone();
+
+        let reverser = VideoReverser::new_with_runner(Box::new(runner));
+        let result = reverser.reverse_video_to(&file_path, &file_path);
+
+        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_output_aliasing_input_rejected_unless_allowed() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        let dotted = dir.path().join("sub").join("..").join("test.mp4");
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let linked = dir.path().join("linked.mp4");
+        fs::hard_link(&file_path, &linked).unwrap();
+
+        let reverser = VideoReverser::new_with_runner(Box::new(MockCommandRunner::new(mock_ok)));
+        for output in [&dotted, &linked] {
+            let result = reverser.reverse_video_to(&file_path, output);
+            assert!(
+                matches!(result, Err(VideoError::InvalidInput(_))),
+                "{:?}",
+                output
+            );
+        }
+
+        let in_place = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(mock_ok)))
+            .allow_in_place(true)
+            .build();
+        in_place.reverse_video_to(&file_path, &file_path).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "reversed");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_reverse_video_async_invalid_input() {
+        let reverser = VideoReverser::new();
+        let result = reverser.reverse_video_async("nonexistent.mp4").await;
+        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+    }
+
+    #[cfg(all(feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_reverse_video_async_cancel() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+        // Stands in for an ffmpeg that takes a long time to reverse
+        let ffmpeg = dir.path().join("ffmpeg");
+        fs::write(
+            &ffmpeg,
+            "#!/bin/sh\n[ \"$1\" = -version ] && echo 'ffmpeg version 6.1' && exit 0\nexec sleep 30\n",
+        )
+        .unwrap();
+        fs::set_permissions(&ffmpeg, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let reverser = VideoReverser::builder()
+            .ffmpeg_path(&ffmpeg)
+            .probe_validation(false)
+            .cancel_token(cancel.clone())
+            .build();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            cancel.store(true, Ordering::Relaxed);
+        });
+
+        let started = Instant::now();
+        let result = reverser.reverse_video_async(&file_path).await;
+
+        canceller.join().unwrap();
+        assert!(matches!(result, Err(VideoError::Cancelled)), "{result:?}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let remaining: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(
+            remaining.len(),
+            2,
+            "only the input and ffmpeg should remain"
+        );
+    }
+
+    // Writes a genuine 1-second 64x64 clip with a test pattern and a tone, or returns
+    // `None` if ffmpeg isn't installed so the calling test can skip itself. `extra_args`
+    // follow the two generated inputs, so they may add further inputs too. The pixel
+    // format is yuv420p unless `extra_args` set one.
+    fn synthesize_clip(dir: &Path, name: &str, extra_args: &[&str]) -> Option<PathBuf> {
+        let have_tools = ["ffmpeg", "ffprobe"].iter().all(|tool| {
+            std::process::Command::new(tool)
+                .arg("-version")
+                .output()
+                .is_ok_and(|output| output.status.success())
+        });
+        if !have_tools {
+            eprintln!("ffmpeg or ffprobe not found; skipping");
+            return None;
+        }
+        let path = dir.join(name);
+        let status = std::process::Command::new("ffmpeg")
+            .args(["-v", "error", "-f", "lavfi", "-i"])
+            .arg("testsrc=duration=1:size=64x64:rate=10")
+            .args(["-f", "lavfi", "-i", "sine=frequency=440:duration=1"])
+            .args(extra_args)
+            .args(if extra_args.contains(&"-pix_fmt") {
+                &[][..]
+            } else {
+                &["-pix_fmt", "yuv420p"][..]
+            })
+            .arg("-shortest")
+            .arg("-y")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        Some(path)
+    }
+
+    // Runs ffprobe on `path` and returns the value of the single entry requested
+    fn ffprobe_entry(path: &Path, args: &[&str]) -> String {
+        let probe = std::process::Command::new("ffprobe")
+            .args(["-v", "error"])
+            .args(args)
+            .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+            .arg(path)
+            .output()
+            .unwrap();
+        assert!(probe.status.success());
+        String::from_utf8_lossy(&probe.stdout).trim().to_string()
+    }
+
+    // The frame count and duration in seconds of the first video stream
+    fn frames_and_duration(path: &Path) -> (u64, f64) {
+        let frames = ffprobe_entry(
+            path,
+            &[
+                "-count_frames",
+                "-select_streams",
+                "v:0",
+                "-show_entries",
+                "stream=nb_read_frames",
+            ],
+        );
+        let duration = ffprobe_entry(path, &["-show_entries", "format=duration"]);
+        (frames.parse().unwrap(), duration.parse().unwrap())
+    }
+
+    // Integration test - runs only where ffmpeg and ffprobe are installed
+    #[test]
+    fn test_successful_video_reverse() {
+        let dir = tempdir().unwrap();
+        let Some(input_path) = synthesize_clip(dir.path(), "input.mp4", &[]) else {
+            return;
+        };
+
+        let output_path = VideoReverser::new().reverse_video(&input_path).unwrap();
+
+        assert_eq!(output_path, dir.path().join("input-rev.mp4"));
+        let (input_frames, input_duration) = frames_and_duration(&input_path);
+        let (output_frames, output_duration) = frames_and_duration(&output_path);
+        assert_eq!(output_frames, input_frames);
+        assert!(
+            (output_duration - input_duration).abs() < 0.1,
+            "{output_duration}s vs {input_duration}s"
+        );
+    }
+
+    // Integration test - runs only where ffmpeg and ffprobe are installed
+    #[test]
+    fn test_reverse_path_with_special_characters() {
+        let dir = tempdir().unwrap();
+        let name = if cfg!(windows) {
+            "my clip é.mp4"
+        } else {
+            "my 'clip' \"é\".mp4"
+        };
+        let Some(input_path) = synthesize_clip(dir.path(), name, &[]) else {
+            return;
+        };
+
+        let output_path = VideoReverser::new().reverse_video(&input_path).unwrap();
+
+        assert_eq!(
+            output_path,
+            dir.path().join(name.replace(".mp4", "-rev.mp4"))
+        );
+        assert_eq!(
+            frames_and_duration(&output_path).0,
+            frames_and_duration(&input_path).0
+        );
+    }
+
+    // Integration test - runs only where ffmpeg and ffprobe are installed
+    #[test]
+    fn test_yuv444p_input_plays_everywhere_after_reversal() {
+        let dir = tempdir().unwrap();
+        let Some(input_path) = synthesize_clip(dir.path(), "graded.mp4", &["-pix_fmt", "yuv444p"])
+        else {
+            return;
+        };
+        let pix_fmt = |path: &Path| {
+            ffprobe_entry(
+                path,
+                &["-select_streams", "v:0", "-show_entries", "stream=pix_fmt"],
+            )
+        };
+        assert_eq!(pix_fmt(&input_path), "yuv444p");
+
+        let outcome = VideoReverser::new()
+            .reverse_video_detailed(&input_path)
+            .unwrap();
+
+        assert_eq!(pix_fmt(&outcome.path), "yuv420p");
+        assert!(outcome
+            .warnings
+            .iter()
+            .any(|w| matches!(w, Warning::PixelFormatConverted { .. })));
+    }
+
+    // Integration test - runs only where ffmpeg and ffprobe are installed
+    #[test]
+    fn test_metadata_round_trips() {
+        let dir = tempdir().unwrap();
+        let Some(input_path) =
+            synthesize_clip(dir.path(), "tagged.mp4", &["-metadata", "title=Round trip"])
+        else {
+            return;
+        };
+
+        let output_path = VideoReverser::new().reverse_video(&input_path).unwrap();
+
+        let title = ffprobe_entry(&output_path, &["-show_entries", "format_tags=title"]);
+        assert_eq!(title, "Round trip");
+    }
+
+    // Integration test - runs only where ffmpeg and ffprobe are installed
+    #[test]
+    fn test_embedded_subtitles_are_dropped() {
+        let dir = tempdir().unwrap();
+        let srt = dir.path().join("cues.srt");
+        fs::write(&srt, "1\n00:00:00,000 --> 00:00:00,900\nHello\n").unwrap();
+        let srt = srt.to_str().unwrap();
+        let extra = [
+            "-i", srt, "-map", "0", "-map", "1", "-map", "2", "-c:s", "mov_text",
+        ];
+        let Some(input_path) = synthesize_clip(dir.path(), "subbed.mp4", &extra) else {
+            return;
+        };
+        let subtitle_streams = |path: &Path| {
+            ffprobe_entry(
+                path,
+                &["-select_streams", "s", "-show_entries", "stream=index"],
+            )
+        };
+        assert!(!subtitle_streams(&input_path).is_empty());
+
+        let output_path = VideoReverser::new().reverse_video(&input_path).unwrap();
+
+        assert_eq!(subtitle_streams(&output_path), "");
+    }
+
+    #[test]
+    fn test_build_ffmpeg_args() {
+        let reverser = VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner::new(|_, _| {
+                panic!("building arguments must not run anything")
+            })))
+            .mode(ReverseMode::VideoOnly)
+            .crf(18)
+            .build();
+
+        let args = reverser.build_ffmpeg_args("in.mov", "out/in-rev.mov");
+
+        assert_eq!(
+            args,
+            [
+                "-i",
+                "in.mov",
+                "-vf",
+                "reverse",
+                "-c:a",
+                "copy",
+                "-crf",
+                "18",
+                "-sn",
+                "-dn",
+                "-map_metadata",
+                "0",
+                "-y",
+                "out/in-rev.mov"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata_mapping_can_be_disabled() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let args = VideoReverser::new().plan_command(&file_path).unwrap().args;
+        let map = args.iter().position(|a| a == "-map_metadata").unwrap();
+        assert_eq!(args[map + 1], "0");
+
+        let stripped = VideoReverser::builder().preserve_metadata(false).build();
+        let args = stripped.plan_command(&file_path).unwrap().args;
+        assert!(!args.iter().any(|a| a == "-map_metadata"));
+    }
+
+    #[test]
+    fn test_quiet_and_verbose_ffmpeg() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.mp4");
+        fs::write(&file_path, "test content").unwrap();
+
+        let quiet = VideoReverser::builder().quiet(true).build();
+        let args = quiet.plan_command(&file_path).unwrap().args;
+        assert_eq!(args[..4], ["-hide_banner", "-loglevel", "error", "-i"]);
+
+        let verbose = VideoReverser::builder().verbose_ffmpeg(true).build();
+        let args = verbose.plan_command(&file_path).unwrap().args;
+        assert_eq!(args[..3], ["-loglevel", "verbose", "-i"]);
+
+        let both = VideoReverser::builder().quiet(true).verbose_ffmpeg(true);
+        assert!(matches!(
+            both.validate(),
+            Err(VideoError::InvalidOptions(_))
+        ));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_real_command_runner_echo_still_captures_stderr() {
+        let control = RunControl {
+            echo_stderr: true,
+            ..RunControl::default()
+        };
+        let output = RealCommandRunner
+            .run_streaming(
+                OsStr::new("sh"),
+                &["-c".into(), "echo out; echo err >&2".into()],
+                &mut |_| {},
+                &control,
+            )
+            .unwrap();
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn test_real_command_runner() {
+        let runner = RealCommandRunner;
+        // Use a command that exists on all major platforms or handle conditionally
+        #[cfg(windows)]
+        let (prog, arg) = ("cmd", "/c echo test");
+        #[cfg(not(windows))]
+        let (prog, arg) = ("echo", "test");
+
+        let args = arg
+            .split_whitespace()
+            .map(OsString::from)
+            .collect::<Vec<_>>();
+        let result = runner.run(OsStr::new(prog), &args);
+        assert!(result.is_ok());
+        let output = result.unwrap();
+        assert!(output.status.success());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_real_command_runner_timeout_kills_child() {
+        let runner = RealCommandRunner;
+        let control = RunControl {
+            timeout: Some(Duration::from_millis(100)),
+            ..RunControl::default()
+        };
+        let started = Instant::now();
+        let result =
+            runner.run_streaming(OsStr::new("sleep"), &["10".into()], &mut |_| {}, &control);
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_real_command_runner_cancel_kills_child() {
+        let runner = RealCommandRunner;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let control = RunControl {
+            cancel: Some(cancel.clone()),
+            ..RunControl::default()
+        };
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            cancel.store(true, Ordering::Relaxed);
+        });
+        let started = Instant::now();
+        let result =
+            runner.run_streaming(OsStr::new("sleep"), &["10".into()], &mut |_| {}, &control);
+        canceller.join().unwrap();
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_real_command_runner_streaming() {
+        let runner = RealCommandRunner;
+        let mut lines = Vec::new();
+        let output = runner
+            .run_streaming(
+                OsStr::new("printf"),
+                &["a\\nb\\n".into()],
+                &mut |line| lines.push(line.to_string()),
+                &RunControl::default(),
+            )
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(lines, vec!["a", "b"]);
+        assert_eq!(output.stdout, b"a\nb\n");
+    }
+}
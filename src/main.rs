@@ -1,350 +1,752 @@
+use clap::{ArgAction, Parser, ValueEnum};
+use mdmp4rev::{
+    BatchStatus, InputCache, OverwritePolicy, ReverseMode, VideoError, VideoReverser,
+    VideoReverserBuilder,
+};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use thiserror::Error;
-
-#[derive(Error, Debug)]
-pub enum VideoError {
-    #[error("FFmpeg is not installed or not accessible")]
-    FFmpegNotFound,
-    #[error("Invalid input file path: {0}")]
-    InvalidInput(String),
-    #[error("Failed to process video: {0}")]
-    ProcessingError(String),
-    #[error("IO error: {0}")]
-    IoError(#[from] std::io::Error),
-}
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Container used when the reversed video is written to stdout
+const STDOUT_FORMAT: &str = "matroska";
+
+/// Exit code when reversing fails, or the options or config are invalid. clap exits
+/// with 2 for a command line it can't parse.
+const FAILURE_EXIT_CODE: i32 = 1;
+
+/// Exit code when some, but not all, videos of a directory failed
+const PARTIAL_FAILURE_EXIT_CODE: i32 = 3;
+
+/// Exit code when every video of a directory failed
+const ALL_FAILED_EXIT_CODE: i32 = 4;
+
+/// Exit code when ffmpeg can't be run at all
+const FFMPEG_NOT_FOUND_EXIT_CODE: i32 = 5;
+
+/// Exit code after a Ctrl-C, as shells report death by SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Config file read from the current directory when `--config` isn't given
+const CONFIG_FILE: &str = "mdmp4rev.toml";
+
+/// Reverse a video with ffmpeg
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Video to reverse, a directory to reverse every video in, or `-` to read from
+    /// stdin and write Matroska to stdout
+    #[arg(required_unless_present = "list_formats")]
+    input: Option<String>,
+
+    /// With a directory input, also reverse the videos in its subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Where to write the result; defaults to the input name with a "-rev" suffix
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Container for the default output name, e.g. `mp4` to write `clip-rev.mp4` from `clip.mkv`
+    #[arg(long, value_name = "EXT")]
+    format: Option<String>,
+
+    /// Write the default output name into this directory instead of next to the input
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+
+    /// Reverse only the video, copying the audio through
+    #[arg(long, conflicts_with = "audio_only")]
+    video_only: bool,
+
+    /// Reverse only the audio, copying the video through
+    #[arg(long)]
+    audio_only: bool,
+
+    /// ffmpeg binary to use instead of the one on PATH
+    #[arg(long)]
+    ffmpeg_path: Option<PathBuf>,
+
+    /// What to do if the output already exists [default: always, or MDMP4REV_OVERWRITE]
+    #[arg(long, value_enum)]
+    overwrite: Option<Overwrite>,
+
+    /// Allow the output to be the input file itself, replacing it
+    #[arg(long)]
+    force: bool,
+
+    /// Check the input and print what would be written, without running ffmpeg
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Log progress; repeat (-vv) to include the ffmpeg command line
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Keep ffmpeg's own log to errors
+    #[arg(short, long, conflicts_with = "verbose_ffmpeg")]
+    quiet: bool,
+
+    /// Show ffmpeg's detailed log on stderr while it runs
+    #[arg(long)]
+    verbose_ffmpeg: bool,
+
+    /// Print the result as a JSON object on stdout, also when reversing fails
+    #[arg(long)]
+    json: bool,
 
-/// Trait to abstract system command execution
-pub trait CommandRunner {
-    fn run(&self, program: &str, args: &[&str]) -> std::io::Result<std::process::Output>;
+    /// List the containers `--format` accepts with this ffmpeg build, and exit
+    #[arg(long)]
+    list_formats: bool,
+
+    /// Read default options from this TOML file instead of `./mdmp4rev.toml`
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
-/// Real implementation using std::process::Command
-pub struct RealCommandRunner;
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Overwrite {
+    Always,
+    Never,
+    Skip,
+}
 
-impl CommandRunner for RealCommandRunner {
-    fn run(&self, program: &str, args: &[&str]) -> std::io::Result<std::process::Output> {
-        Command::new(program).args(args).output()
+impl From<Overwrite> for OverwritePolicy {
+    fn from(overwrite: Overwrite) -> Self {
+        match overwrite {
+            Overwrite::Always => OverwritePolicy::Always,
+            Overwrite::Never => OverwritePolicy::Never,
+            Overwrite::Skip => OverwritePolicy::Skip,
+        }
     }
 }
 
-pub struct VideoReverser {
-    runner: Box<dyn CommandRunner>,
+/// Shared defaults from `mdmp4rev.toml`. They override the `MDMP4REV_*` environment
+/// variables and are overridden by command-line flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    ffmpeg_path: Option<PathBuf>,
+    crf: Option<u8>,
+    preset: Option<String>,
+    overwrite: Option<Overwrite>,
+    allowed_extensions: Option<Vec<String>>,
+    output_template: Option<String>,
 }
 
-impl Default for VideoReverser {
-    fn default() -> Self {
-        Self::new()
+impl Config {
+    /// Reads `path`, or [`CONFIG_FILE`] if it exists when no path is given
+    fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(CONFIG_FILE).is_file() => Path::new(CONFIG_FILE),
+            None => return Ok(Self::default()),
+        };
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Can't read config {}: {e}", path.display()))?;
+        Ok(toml::from_str(&text).map_err(|e| format!("Invalid config {}: {e}", path.display()))?)
+    }
+
+    fn apply(self, mut builder: VideoReverserBuilder) -> VideoReverserBuilder {
+        if let Some(ffmpeg_path) = self.ffmpeg_path {
+            builder = builder.ffmpeg_path(ffmpeg_path);
+        }
+        if let Some(crf) = self.crf {
+            builder = builder.crf(crf);
+        }
+        if let Some(preset) = self.preset {
+            builder = builder.preset(preset);
+        }
+        if let Some(overwrite) = self.overwrite {
+            builder = builder.overwrite(overwrite.into());
+        }
+        if let Some(extensions) = self.allowed_extensions {
+            builder = builder.allowed_extensions(extensions);
+        }
+        if let Some(template) = self.output_template {
+            builder = builder.output_template(template);
+        }
+        builder
     }
 }
 
-impl VideoReverser {
-    /// Creates a new VideoReverser instance with default runner
-    pub fn new() -> Self {
-        Self {
-            runner: Box::new(RealCommandRunner),
+impl Cli {
+    /// The input argument, which clap requires unless `--list-formats` is given
+    fn input(&self) -> &str {
+        self.input.as_deref().unwrap_or_default()
+    }
+
+    fn mode(&self) -> ReverseMode {
+        if self.video_only {
+            ReverseMode::VideoOnly
+        } else if self.audio_only {
+            ReverseMode::AudioOnly
+        } else {
+            ReverseMode::Both
         }
     }
 
-    /// Creates a new VideoReverser with a specific runner (useful for testing)
-    pub fn new_with_runner(runner: Box<dyn CommandRunner>) -> Self {
-        Self { runner }
+    /// Default log filter when `RUST_LOG` isn't set
+    fn log_level(&self) -> &'static str {
+        match self.verbose {
+            0 => "warn",
+            1 => "info",
+            _ => "debug",
+        }
     }
+}
+
+fn run(cli: Cli, cancel: Arc<AtomicBool>) -> Result<(), Box<dyn std::error::Error>> {
+    run_with_builder(cli, VideoReverser::builder().cancel_token(cancel))
+}
 
-    /// Checks if ffmpeg is available on the system
-    fn check_ffmpeg(&self) -> Result<(), VideoError> {
-        match self.runner.run("ffmpeg", &["-version"]) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(VideoError::FFmpegNotFound),
+fn run_with_builder(
+    cli: Cli,
+    builder: VideoReverserBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let builder = Config::load(cli.config.as_deref())?.apply(builder);
+    let reverser = configure(&cli, builder);
+    if cli.list_formats {
+        let formats = reverser.supported_output_formats()?;
+        if cli.json {
+            println!("{}", serde_json::to_string(&formats)?);
+        } else {
+            formats.iter().for_each(|format| println!("{format}"));
         }
+        return Ok(());
     }
 
-    /// Generates the output filename by appending "-rev" before the extension
-    fn generate_output_filename(&self, input_path: &Path) -> PathBuf {
-        let stem = input_path.file_stem().unwrap_or_default();
-        let extension = input_path.extension().unwrap_or_default();
-        let mut new_name = stem.to_os_string();
-        new_name.push("-rev");
-        let mut output_path = input_path.with_file_name(new_name);
-        output_path.set_extension(extension);
-        output_path
+    if Path::new(cli.input()).is_dir() {
+        return reverse_batch(&cli, &reverser);
     }
 
-    /// Reverses the input MP4 file
-    pub fn reverse_video<P: AsRef<Path>>(&self, input_path: P) -> Result<PathBuf, VideoError> {
-        let input_path = input_path.as_ref();
+    let started = Instant::now();
+    let result = reverse(&cli, &reverser);
+    if cli.json {
+        println!("{}", json_report(cli.input(), &result, started.elapsed()));
+    } else if let Ok(Some(output_path)) = &result {
+        if cli.dry_run {
+            println!("Would create reversed video: {:?}", output_path);
+        } else {
+            println!("Successfully created reversed video: {:?}", output_path);
+        }
+    }
+    result.map(|_| ())
+}
 
-        // Validate input file
-        if !input_path.exists() {
-            return Err(VideoError::InvalidInput(
-                "Input file does not exist".to_string(),
-            ));
+/// Applies the options in `cli` to `builder`
+fn configure(cli: &Cli, builder: VideoReverserBuilder) -> VideoReverser {
+    let mut builder = builder
+        .mode(cli.mode())
+        .allow_in_place(cli.force)
+        .dry_run(cli.dry_run)
+        .quiet(cli.quiet)
+        .verbose_ffmpeg(cli.verbose_ffmpeg)
+        .recursive(cli.recursive);
+    if let Some(overwrite) = cli.overwrite {
+        builder = builder.overwrite(overwrite.into());
+    }
+    if let Some(format) = &cli.format {
+        builder = builder.output_format(format);
+    }
+    if let Some(output_dir) = &cli.output_dir {
+        builder = builder.output_dir(output_dir);
+    }
+    if let Some(ffmpeg_path) = &cli.ffmpeg_path {
+        builder = builder.ffmpeg_path(ffmpeg_path);
+    }
+    builder.build()
+}
+
+/// Some videos of a batch failed; each was already reported on stderr
+#[derive(Debug)]
+struct BatchFailed {
+    failed: usize,
+    total: usize,
+}
+
+impl std::fmt::Display for BatchFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} of {} videos failed", self.failed, self.total)
+    }
+}
+
+impl std::error::Error for BatchFailed {}
+
+/// Reverses every video in the directory `cli.input()`, reporting each failure on
+/// stderr and finishing with a summary line
+fn reverse_batch(cli: &Cli, reverser: &VideoReverser) -> Result<(), Box<dyn std::error::Error>> {
+    if cli.output.is_some() {
+        return Err("--output can't be used with a directory; use --output-dir".into());
+    }
+    if cli.json {
+        return Err("--json can't be used with a directory".into());
+    }
+    // Nothing is kept between runs, so only the overwrite policy skips inputs
+    let results = reverser.reverse_directory_incremental(cli.input(), &mut InputCache::default());
+    let (mut reversed, mut skipped, mut failed) = (0, 0, 0);
+    for (input, result) in &results {
+        match result {
+            Ok(BatchStatus::Reversed(output)) => {
+                reversed += 1;
+                if cli.dry_run {
+                    println!("Would create reversed video: {:?}", output);
+                } else {
+                    println!("Successfully created reversed video: {:?}", output);
+                }
+            }
+            Ok(BatchStatus::Skipped(_)) => skipped += 1,
+            // Every other input would fail the same way
+            Err(VideoError::FFmpegNotFound) => return Err(VideoError::FFmpegNotFound.into()),
+            // Ctrl-C ends the batch, which starts nothing after it
+            Err(VideoError::Cancelled) => return Err(VideoError::Cancelled.into()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("Error: {}: {}", input.display(), e);
+            }
         }
+    }
+    println!("Reversed {reversed}, skipped {skipped}, failed {failed}");
+    if failed > 0 {
+        return Err(BatchFailed {
+            failed,
+            total: results.len(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The exit code for a run that failed with `error`, unless it was interrupted
+fn exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if let Some(batch) = error.downcast_ref::<BatchFailed>() {
+        return if batch.failed == batch.total {
+            ALL_FAILED_EXIT_CODE
+        } else {
+            PARTIAL_FAILURE_EXIT_CODE
+        };
+    }
+    match error.downcast_ref() {
+        Some(VideoError::FFmpegNotFound) => FFMPEG_NOT_FOUND_EXIT_CODE,
+        _ => FAILURE_EXIT_CODE,
+    }
+}
 
-        // Check file extension
-        if input_path.extension().and_then(|ext| ext.to_str()) != Some("mp4") {
-            return Err(VideoError::InvalidInput(
-                "Input file must be an MP4".to_string(),
-            ));
+/// Reverses as `cli` asks, returning the output path, or `None` when writing to stdout
+fn reverse(
+    cli: &Cli,
+    reverser: &VideoReverser,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    // `-` reads from stdin and writes to stdout, so nothing else may be printed there
+    if cli.input() == "-" {
+        if cli.output.is_some() {
+            return Err("--output can't be used when reading from stdin".into());
         }
+        if cli.json {
+            return Err("--json can't be used when writing the video to stdout".into());
+        }
+        reverser.reverse_stream(STDOUT_FORMAT)?;
+        return Ok(None);
+    }
 
-        // Check if ffmpeg is available
-        self.check_ffmpeg()?;
+    // The bar is drawn on stderr, but only makes sense when someone is watching and
+    // ffmpeg isn't writing there too
+    if !cli.json && !cli.dry_run && !cli.verbose_ffmpeg && std::io::stdout().is_terminal() {
+        return Ok(Some(reverse_with_bar(cli, reverser)?));
+    }
+    let output_path = match &cli.output {
+        Some(output) => reverser.reverse_video_to(cli.input(), output)?,
+        None => reverser.reverse_video(cli.input())?,
+    };
+    Ok(Some(output_path))
+}
 
-        let output_path = self.generate_output_filename(input_path);
+#[cfg(feature = "progress-bar")]
+fn reverse_with_bar(cli: &Cli, reverser: &VideoReverser) -> Result<PathBuf, mdmp4rev::VideoError> {
+    let bar = progress::ProgressBar::new(reverser.input_duration(cli.input()));
+    let update = |fraction| bar.update(fraction);
+    let result = match &cli.output {
+        Some(output) => reverser.reverse_video_to_with_progress(cli.input(), output, update),
+        None => reverser.reverse_video_with_progress(cli.input(), update),
+    };
+    bar.finish();
+    result
+}
 
-        // Execute ffmpeg command to reverse the video
-        let args = [
-            "-i",
-            input_path.to_str().unwrap(),
-            "-vf",
-            "reverse",
-            "-af",
-            "areverse",
-            "-y",
-            output_path.to_str().unwrap(),
-        ];
+#[cfg(not(feature = "progress-bar"))]
+fn reverse_with_bar(cli: &Cli, reverser: &VideoReverser) -> Result<PathBuf, mdmp4rev::VideoError> {
+    match &cli.output {
+        Some(output) => reverser.reverse_video_to(cli.input(), output),
+        None => reverser.reverse_video(cli.input()),
+    }
+}
+
+#[cfg(feature = "progress-bar")]
+mod progress {
+    use indicatif::ProgressStyle;
+    use std::time::Duration;
 
-        let result = self.runner.run("ffmpeg", &args)?;
+    /// Steps of the bar; the library reports fractions
+    const STEPS: u64 = 1000;
 
-        if !result.status.success() {
-            return Err(VideoError::ProcessingError(
-                String::from_utf8_lossy(&result.stderr).to_string(),
-            ));
+    /// Shows percent, ETA and, when the input length is known, the timestamp reached
+    pub struct ProgressBar {
+        bar: indicatif::ProgressBar,
+        total: Option<Duration>,
+    }
+
+    impl ProgressBar {
+        pub fn new(total: Option<Duration>) -> Self {
+            let bar = indicatif::ProgressBar::new(STEPS);
+            bar.set_style(
+                ProgressStyle::with_template("{bar:40} {percent:>3}% {msg} (ETA {eta})")
+                    .expect("template is valid"),
+            );
+            Self { bar, total }
         }
 
-        Ok(output_path)
+        pub fn update(&self, fraction: f64) {
+            self.bar.set_position((fraction * STEPS as f64) as u64);
+            if let Some(total) = self.total {
+                self.bar.set_message(format!(
+                    "{} / {}",
+                    timestamp(total.mul_f64(fraction)),
+                    timestamp(total)
+                ));
+            }
+        }
+
+        pub fn finish(&self) {
+            self.bar.finish_and_clear();
+        }
+    }
+
+    /// Formats as `HH:MM:SS`
+    pub fn timestamp(time: Duration) -> String {
+        let seconds = time.as_secs();
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// What `--json` prints
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    input: &'a str,
+    output: Option<String>,
+    status: &'static str,
+    error: Option<String>,
+    duration_ms: u64,
+}
+
+fn json_report(
+    input: &str,
+    result: &Result<Option<PathBuf>, Box<dyn std::error::Error>>,
+    elapsed: Duration,
+) -> String {
+    let (output, error) = match result {
+        Ok(output) => (output.as_deref().map(Path::to_string_lossy), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let report = Report {
+        input,
+        output: output.map(|output| output.into_owned()),
+        status: if error.is_none() { "ok" } else { "error" },
+        error,
+        duration_ms: elapsed.as_millis() as u64,
+    };
+    // Only strings and numbers, which always serialize
+    serde_json::to_string(&report).expect("report serializes")
+}
+
+fn main() {
+    let cli = Cli::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(cli.log_level()))
+        .init();
+
+    // Ctrl-C stops ffmpeg through the cancel token, which also removes the partial output
+    let cancel = Arc::new(AtomicBool::new(false));
+    let handler_cancel = cancel.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_cancel.store(true, Ordering::Relaxed)) {
+        log::warn!("Ctrl-C won't clean up partial output: {}", e);
+    }
+
+    if let Err(e) = run(cli, cancel.clone()) {
+        eprintln!("Error: {}", e);
+        if let Some(VideoError::ProcessingError { command, .. }) = e.downcast_ref() {
+            eprintln!("Command: {command}");
+        }
+        let interrupted = cancel.load(Ordering::Relaxed);
+        std::process::exit(if interrupted {
+            INTERRUPTED_EXIT_CODE
+        } else {
+            exit_code(e.as_ref())
+        });
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::cell::RefCell;
+    use mdmp4rev::CommandRunner;
+    use std::ffi::{OsStr, OsString};
     use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    #[cfg(windows)]
     use std::os::windows::process::ExitStatusExt;
-    use std::rc::Rc;
+    use std::path::Path;
+    use std::process::{ExitStatus, Output};
     use tempfile::tempdir;
 
-    // Mock runner for testing
+    // Stands in for ffmpeg: fails with `stderr` if set, otherwise writes the output file
     struct MockCommandRunner {
-        // We use RefCell to allow interior mutability for tracking calls
-        calls: Rc<RefCell<Vec<(String, Vec<String>)>>>,
-        // Closures to determine behavior based on command
-        behavior: Rc<dyn Fn(&str, &[&str]) -> std::io::Result<std::process::Output>>,
+        stderr: Option<&'static str>,
     }
 
     impl CommandRunner for MockCommandRunner {
-        fn run(&self, program: &str, args: &[&str]) -> std::io::Result<std::process::Output> {
-            self.calls.borrow_mut().push((
-                program.to_string(),
-                args.iter().map(|s| s.to_string()).collect(),
-            ));
-            (self.behavior)(program, args)
+        fn run(&self, _program: &OsStr, args: &[OsString]) -> std::io::Result<Output> {
+            let output_path = Path::new(args.last().unwrap());
+            let code = match self.stderr {
+                Some(_) => 1,
+                None => {
+                    if output_path.is_absolute() {
+                        fs::write(output_path, "reversed")?;
+                    }
+                    0
+                }
+            };
+            Ok(Output {
+                status: exit_status(code),
+                stdout: Vec::new(),
+                stderr: self.stderr.unwrap_or_default().as_bytes().to_vec(),
+            })
         }
     }
 
-    impl MockCommandRunner {
-        fn new(
-            behavior: impl Fn(&str, &[&str]) -> std::io::Result<std::process::Output> + 'static,
-        ) -> Self {
-            Self {
-                calls: Rc::new(RefCell::new(Vec::new())),
-                behavior: Rc::new(behavior),
-            }
-        }
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
     }
 
-    fn mock_success() -> std::process::Output {
-        std::process::Output {
-            status: std::process::ExitStatus::from_raw(0),
-            stdout: Vec::new(),
-            stderr: Vec::new(),
-        }
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code as u32)
     }
 
-    fn mock_failure(stderr: &str) -> std::process::Output {
-        std::process::Output {
-            status: std::process::ExitStatus::from_raw(1),
-            stdout: Vec::new(),
-            stderr: stderr.as_bytes().to_vec(),
-        }
+    fn mock_builder(stderr: Option<&'static str>) -> VideoReverserBuilder {
+        VideoReverser::builder()
+            .runner(Box::new(MockCommandRunner { stderr }))
+            .probe_validation(false)
+    }
+
+    fn run_mock(
+        args: &[&str],
+        stderr: Option<&'static str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let cli = Cli::try_parse_from(args)?;
+        run_with_builder(cli, mock_builder(stderr))
     }
 
     #[test]
-    fn test_generate_output_filename() {
-        let reverser = VideoReverser::new();
-        let input = Path::new("test.mp4");
-        let output = reverser.generate_output_filename(input);
-        assert_eq!(output.to_str().unwrap(), "test-rev.mp4");
+    fn test_run_usage() {
+        let err = Cli::try_parse_from(["mdmp4rev"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+        assert!(err.to_string().contains("Usage: mdmp4rev"));
     }
 
     #[test]
-    fn test_invalid_input_file() {
-        let reverser = VideoReverser::new();
-        let result = reverser.reverse_video("nonexistent.mp4");
-        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+    fn test_cli_flags_map_to_options() {
+        let cli = Cli::try_parse_from(["mdmp4rev", "--audio-only", "-vv", "in.mp4"]).unwrap();
+        assert_eq!(cli.mode(), ReverseMode::AudioOnly);
+        assert_eq!(cli.log_level(), "debug");
+        assert!(
+            Cli::try_parse_from(["mdmp4rev", "--audio-only", "--video-only", "in.mp4"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["mdmp4rev", "-q", "--verbose-ffmpeg", "in.mp4"]).is_err());
     }
 
     #[test]
-    fn test_invalid_file_extension() {
+    fn test_config_file() {
         let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.txt");
-        fs::write(&file_path, "test content").unwrap();
+        let config_path = dir.path().join("team.toml");
+        fs::write(
+            &config_path,
+            "crf = 20\npreset = \"slow\"\noverwrite = \"never\"\n\
+             allowed_extensions = [\"mp4\", \"mts\"]\noutput_template = \"{stem}_back.{ext}\"\n",
+        )
+        .unwrap();
+        let config = Config::load(Some(&config_path)).unwrap();
+        assert_eq!(config.crf, Some(20));
+        assert_eq!(config.preset.as_deref(), Some("slow"));
+        assert!(matches!(config.overwrite, Some(Overwrite::Never)));
+        assert_eq!(
+            config.allowed_extensions,
+            Some(vec!["mp4".to_string(), "mts".to_string()])
+        );
 
-        let reverser = VideoReverser::new();
-        let result = reverser.reverse_video(&file_path);
-        assert!(matches!(result, Err(VideoError::InvalidInput(_))));
+        let file_path = dir.path().join("test.mts");
+        fs::write(&file_path, "test content").unwrap();
+        let config_arg = config_path.to_str().unwrap();
+        let output = dir.path().join("test_back.mts");
+        assert!(run_mock(
+            &[
+                "mdmp4rev",
+                "--config",
+                config_arg,
+                file_path.to_str().unwrap()
+            ],
+            None
+        )
+        .is_ok());
+        assert!(output.exists());
+
+        // The file says never, the flag says always
+        assert!(run_mock(
+            &[
+                "mdmp4rev",
+                "--config",
+                config_arg,
+                file_path.to_str().unwrap()
+            ],
+            None
+        )
+        .is_err());
+        let args = [
+            "mdmp4rev",
+            "--config",
+            config_arg,
+            "--overwrite",
+            "always",
+            file_path.to_str().unwrap(),
+        ];
+        assert!(run_mock(&args, None).is_ok());
+
+        fs::write(&config_path, "crf = \"high\"\n").unwrap();
+        let err = Config::load(Some(&config_path)).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid config"));
+        fs::write(&config_path, "threads = 4\n").unwrap();
+        assert!(Config::load(Some(&config_path)).is_err());
+        assert!(Config::load(Some(&dir.path().join("missing.toml"))).is_err());
     }
 
     #[test]
-    fn test_ffmpeg_not_found_mock() {
+    fn test_batch_exit_codes() {
         let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.mp4");
-        fs::write(&file_path, "test content").unwrap();
+        for name in ["a.mp4", "b.mp4"] {
+            fs::write(dir.path().join(name), "test content").unwrap();
+        }
+        let input = dir.path().to_str().unwrap();
+
+        assert!(run_mock(&["mdmp4rev", input], None).is_ok());
+        assert!(dir.path().join("a-rev.mp4").exists());
+
+        let err =
+            run_mock(&["mdmp4rev", "--overwrite", "always", input], Some("boom")).unwrap_err();
+        assert_eq!(err.to_string(), "2 of 2 videos failed");
+        assert_eq!(exit_code(err.as_ref()), ALL_FAILED_EXIT_CODE);
+        let partial = BatchFailed {
+            failed: 1,
+            total: 2,
+        };
+        assert_eq!(exit_code(&partial), PARTIAL_FAILURE_EXIT_CODE);
+        assert_eq!(
+            exit_code(&VideoError::FFmpegNotFound),
+            FFMPEG_NOT_FOUND_EXIT_CODE
+        );
+        assert_eq!(exit_code(&VideoError::Cancelled), FAILURE_EXIT_CODE);
 
-        // Mock runner that fails on -version check
-        let runner = MockCommandRunner::new(|program, args| {
-            if program == "ffmpeg" && args.contains(&"-version") {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "not found",
-                ))
-            } else {
-                Ok(mock_success())
-            }
-        });
+        assert!(run_mock(&["mdmp4rev", "-o", "out.mp4", input], None).is_err());
 
-        let reverser = VideoReverser::new_with_runner(Box::new(runner));
-        let result = reverser.reverse_video(&file_path);
-        assert!(matches!(result, Err(VideoError::FFmpegNotFound)));
+        // After Ctrl-C no further video is started
+        let cli = Cli::try_parse_from(["mdmp4rev", "--overwrite", "always", input]).unwrap();
+        fs::remove_file(dir.path().join("a-rev.mp4")).unwrap();
+        let cancelled = mock_builder(None).cancel_token(Arc::new(AtomicBool::new(true)));
+        let err = run_with_builder(cli, cancelled).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(VideoError::Cancelled)));
+        assert!(!dir.path().join("a-rev.mp4").exists());
     }
 
     #[test]
-    fn test_successful_video_reverse_mock() {
+    fn test_run_success() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test.mp4");
         fs::write(&file_path, "test content").unwrap();
-        let expected_output = dir.path().join("test-rev.mp4");
-
-        // Mock runner that succeeds
-        let runner = MockCommandRunner::new(|_, _| Ok(mock_success()));
-        let calls = runner.calls.clone();
-
-        let reverser = VideoReverser::new_with_runner(Box::new(runner));
-        let result = reverser.reverse_video(&file_path);
 
+        let result = run_mock(&["mdmp4rev", file_path.to_str().unwrap()], None);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), expected_output);
-
-        // Verify calls
-        let calls = calls.borrow();
-        // 1. check_ffmpeg
-        assert_eq!(calls[0].0, "ffmpeg");
-        assert_eq!(calls[0].1, vec!["-version"]);
-        // 2. reverse_video
-        assert_eq!(calls[1].0, "ffmpeg");
-        assert!(calls[1].1.contains(&"-i".to_string()));
-        assert!(calls[1]
-            .1
-            .contains(&file_path.to_str().unwrap().to_string()));
+        assert!(dir.path().join("test-rev.mp4").exists());
     }
 
     #[test]
-    fn test_ffmpeg_process_failure_mock() {
+    fn test_run_with_output_and_overwrite() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("test.mp4");
+        let output_path = dir.path().join("out.mp4");
         fs::write(&file_path, "test content").unwrap();
+        fs::write(&output_path, "old").unwrap();
 
-        // Mock runner that fails on conversion but passes version check
-        let runner = MockCommandRunner::new(|_, args| {
-            if args.contains(&"-version") {
-                Ok(mock_success())
-            } else {
-                Ok(mock_failure("Conversion failed"))
-            }
-        });
-
-        let reverser = VideoReverser::new_with_runner(Box::new(runner));
-        let result = reverser.reverse_video(&file_path);
-
-        match result {
-            Err(VideoError::ProcessingError(msg)) => assert_eq!(msg, "Conversion failed"),
-            _ => panic!("Expected ProcessingError"),
-        }
+        let args = [
+            "mdmp4rev",
+            file_path.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+            "--overwrite",
+            "never",
+        ];
+        let result = run_mock(&args, None);
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), "old");
     }
 
-    // Integration test - requires ffmpeg to be installed
     #[test]
-    #[ignore] // Run with `cargo test -- --ignored`
-    fn test_successful_video_reverse() {
-        let dir = tempdir().unwrap();
-        let input_path = dir.path().join("input.mp4");
-
-        // Create a dummy MP4 file (not actually valid, just for testing)
-        fs::write(&input_path, "dummy mp4 content").unwrap();
-
-        let reverser = VideoReverser::new();
-        let result = reverser.reverse_video(&input_path);
-
-        // Since we are mocking real ffmpeg now, we don't expect it to actually work on a dummy file if ffmpeg is real
-        // But for an integration test, we might expect failure if the file is invalid for ffmpeg.
-        // However, if we just want to test 'VideoReverser' with 'RealCommandRunner' (which is default), this test stands.
-        // NOTE: A dummy file "dummy mp4 content" will likely cause ffmpeg to fail with "Invalid data found",
-        // leading to ProcessingError, not Ok.
-        // So this test as written was likely flaky or relied on ffmpeg ignoring garbage input (which it doesn't).
-        // We will assert that it runs, but likely fails.
-        // Or we can just keep it ignored.
-
-        // For the purpose of this refactor, let's leave it ignored but structurally correct.
-        // If we wanted it to pass, we'd need a real valid MP4 or allow failure.
-        let _ = result;
+    fn test_run_stdin_to_stdout() {
+        let result = run_mock(&["mdmp4rev", "-"], None);
+        assert!(result.is_ok());
     }
 
     #[test]
-    fn test_run_usage() {
-        let args = vec!["mdmp4rev".to_string()];
-        let result = run(args);
-        assert!(result.is_err());
+    fn test_json_report() {
+        let ok = json_report(
+            "in.mp4",
+            &Ok(Some(PathBuf::from("in-rev.mp4"))),
+            Duration::from_millis(1234),
+        );
         assert_eq!(
-            result.unwrap_err().to_string(),
-            "Usage: mdmp4rev <input_mp4_file>"
+            ok,
+            r#"{"input":"in.mp4","output":"in-rev.mp4","status":"ok","error":null,"duration_ms":1234}"#
         );
+
+        let failed = json_report("in.mp4", &Err("bad \"input\"".into()), Duration::ZERO);
+        let parsed: serde_json::Value = serde_json::from_str(&failed).unwrap();
+        assert_eq!(parsed["status"], "error");
+        assert_eq!(parsed["error"], "bad \"input\"");
+        assert!(parsed["output"].is_null());
     }
 
+    #[cfg(feature = "progress-bar")]
     #[test]
-    fn test_real_command_runner() {
-        let runner = RealCommandRunner;
-        // Use a command that exists on all major platforms or handle conditionally
-        #[cfg(windows)]
-        let (prog, arg) = ("cmd", "/c echo test");
-        #[cfg(not(windows))]
-        let (prog, arg) = ("echo", "test");
-
-        let args = arg.split_whitespace().collect::<Vec<_>>();
-        let result = runner.run(prog, &args);
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        assert!(output.status.success());
+    fn test_progress_timestamp() {
+        assert_eq!(
+            progress::timestamp(Duration::from_secs_f64(83.9)),
+            "00:01:23"
+        );
+        assert_eq!(progress::timestamp(Duration::from_secs(3725)), "01:02:05");
     }
 
     #[test]
-    fn test_run_success() {
-        let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.mp4");
-        fs::write(&file_path, "test content").unwrap();
-
-        let args = vec![
-            "mdmp4rev".to_string(),
-            file_path.to_str().unwrap().to_string(),
-        ];
-
-        let runner = MockCommandRunner::new(|_, _| Ok(mock_success()));
-        let reverser = VideoReverser::new_with_runner(Box::new(runner));
-
-        // We need to call run_internal directly
-        let result = run_with_reverser(args, reverser);
-        assert!(result.is_ok());
+    fn test_list_formats_needs_no_input() {
+        let cli = Cli::try_parse_from(["mdmp4rev", "--list-formats"]).unwrap();
+        assert!(cli.list_formats);
+        assert!(cli.input.is_none());
+        assert!(run_with_builder(cli, mock_builder(None)).is_ok());
     }
 
     #[test]
@@ -353,15 +755,10 @@ mod tests {
         let file_path = dir.path().join("test.mp4");
         fs::write(&file_path, "test content").unwrap();
 
-        let args = vec![
-            "mdmp4rev".to_string(),
-            file_path.to_str().unwrap().to_string(),
-        ];
-
-        let runner = MockCommandRunner::new(|_, _| Ok(mock_failure("processing failed")));
-        let reverser = VideoReverser::new_with_runner(Box::new(runner));
-
-        let result = run_with_reverser(args, reverser);
+        let result = run_mock(
+            &["mdmp4rev", file_path.to_str().unwrap()],
+            Some("processing failed"),
+        );
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -369,33 +766,3 @@ mod tests {
             .contains("processing failed"));
     }
 }
-
-pub fn run(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    run_with_reverser(args, VideoReverser::new())
-}
-
-fn run_with_reverser(
-    args: Vec<String>,
-    reverser: VideoReverser,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if args.len() != 2 {
-        return Err(format!("Usage: {} <input_mp4_file>", args[0]).into());
-    }
-
-    match reverser.reverse_video(&args[1]) {
-        Ok(output_path) => {
-            println!("Successfully created reversed video: {:?}", output_path);
-            Ok(())
-        }
-        Err(e) => Err(Box::new(e)),
-    }
-}
-
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-
-    if let Err(e) = run(args) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
-}